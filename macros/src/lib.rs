@@ -8,12 +8,13 @@ use syn::{
 /// Parsed arguments for the `#[hf_test]` attribute.
 ///
 /// Expected format:
-///   `#[hf_test(model = "org/model", cleanup = false, cache_dir = "/path/to/cache", ignore = "reason")]`
+///   `#[hf_test(model = "org/model", cleanup = false, cache_dir = "/path/to/cache", ignore = "reason", gguf = "model.gguf")]`
 struct HfTestArgs {
     model: String,
     cleanup: bool,
     cache_dir: Option<String>,
     ignore: Option<String>,
+    gguf: Option<String>,
 }
 
 impl Parse for HfTestArgs {
@@ -22,6 +23,7 @@ impl Parse for HfTestArgs {
         let mut cleanup = None;
         let mut cache_dir = None;
         let mut ignore = None;
+        let mut gguf = None;
 
         while !input.is_empty() {
             let key: Ident = input.parse()?;
@@ -39,11 +41,14 @@ impl Parse for HfTestArgs {
             } else if key == "ignore" {
                 let value: LitStr = input.parse()?;
                 ignore = Some(value.value());
+            } else if key == "gguf" {
+                let value: LitStr = input.parse()?;
+                gguf = Some(value.value());
             } else {
                 return Err(syn::Error::new(
                     key.span(),
                     format!(
-                        "unknown argument `{key}`, expected `model`, `cleanup`, `cache_dir`, or `ignore`"
+                        "unknown argument `{key}`, expected `model`, `cleanup`, `cache_dir`, `ignore`, or `gguf`"
                     ),
                 ));
             }
@@ -58,6 +63,7 @@ impl Parse for HfTestArgs {
             cleanup: cleanup.unwrap_or(false),
             cache_dir,
             ignore,
+            gguf,
         })
     }
 }
@@ -70,6 +76,10 @@ impl Parse for HfTestArgs {
 /// - `cleanup` — whether to remove the model from disk after the test (optional, defaults to `false`)
 /// - `cache_dir` — path where the HF cache stores/downloads models (optional)
 /// - `ignore` — reason string to ignore this test (optional, generates `#[ignore = "reason"]`)
+/// - `gguf` — filename of a quantized `*.gguf` weight file to fetch from the repo instead of
+///   resolving `*.safetensors` (optional). When set and a cache directory is known (from
+///   `cache_dir` or `HF_TEST_CACHE_DIR`), `model_file` on the generated config points at the
+///   cached `.gguf` path; with `hf_hub` defaults the file is neither fetched nor applied.
 ///
 /// # Cache Directory Resolution
 ///
@@ -173,6 +183,36 @@ pub fn hf_test(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
+    // When `gguf` is given, skip the safetensors resolution entirely and
+    // point the generated config's `model_file` at the cached `.gguf` path.
+    let (config_let, gguf_init, gguf_override) = if let Some(gguf) = &args.gguf {
+        (
+            quote! { let mut #config_ident },
+            quote! {
+                let __hf_gguf_file: std::option::Option<&str> = std::option::Option::Some(#gguf);
+            },
+            quote! {
+                if let Some(ref cache_dir) = __hf_cache_dir_opt {
+                    let cache = hf_hub::Cache::new(cache_dir.clone());
+                    let cached_gguf = cache.model(#model_id.to_string()).get(#gguf).ok_or_else(
+                        || -> Box<dyn std::error::Error> {
+                            format!("gguf file `{}` not found in cache", #gguf).into()
+                        },
+                    )?;
+                    #config_ident.model_file = std::option::Option::Some(cached_gguf);
+                }
+            },
+        )
+    } else {
+        (
+            quote! { let #config_ident },
+            quote! {
+                let __hf_gguf_file: std::option::Option<&str> = std::option::Option::None;
+            },
+            quote! {},
+        )
+    };
+
     let output = quote! {
         #[test]
         #ignore_attr
@@ -180,17 +220,20 @@ pub fn hf_test(attr: TokenStream, item: TokenStream) -> TokenStream {
         #fn_vis fn #fn_name() -> std::result::Result<(), Box<dyn std::error::Error>> {
 
             #cache_dir_init
+            #gguf_init
 
             // Only ensure download if we have an explicit cache directory
             if let Some(ref cache_dir) = __hf_cache_dir_opt {
-                common::ensure_model_downloaded(#model_id, cache_dir)?;
+                common::ensure_model_downloaded(#model_id, cache_dir, __hf_gguf_file)?;
             }
 
-            let #config_ident = tauri_plugin_llm::LLMRuntimeConfig::from_hf_local_cache(
+            #config_let = tauri_plugin_llm::LLMRuntimeConfig::from_hf_local_cache(
                 #model_id,
                 __hf_cache_dir_opt.as_ref(),
             )?;
 
+            #gguf_override
+
             // Only set up cleanup guard if we have a cache_dir and cleanup is enabled
             let __hf_guard = if #cleanup {
                 __hf_cache_dir_opt.map(|cache_dir| common::HfModelGuard::new(
@@ -1,16 +1,237 @@
+use crate::Error;
+use crate::LlmMessage;
 use crate::Result;
 use crate::{models::*, PluginState};
 use tauri::command;
 use tauri::State;
 
+/// Enqueues `message` on the long-lived model worker and returns whatever
+/// reply is already waiting (typically a [`LlmMessage::Chunk`] or the final
+/// [`LlmMessage::Response`]). Call [`retry_recv`] to keep polling for the
+/// rest of a streamed generation.
 #[command]
 pub(crate) async fn send_message(state: State<'_, PluginState>, message: Query) -> Result<Query> {
-    let runtime = state.runtime.lock().unwrap();
-    runtime.send(message)
+    let prompt = query_to_prompt(message)?;
+
+    let response = {
+        let runtime = state.runtime.lock().unwrap();
+        runtime.send(prompt)?
+    };
+
+    response_to_query(response)
 }
 
+/// Polls the worker for the next chunk of a generation started by
+/// [`send_message`], without blocking if nothing has arrived yet.
 #[command]
 pub(crate) async fn retry_recv(state: State<'_, PluginState>) -> Result<Query> {
-    let runtime = state.runtime.lock().unwrap();
-    runtime.retry_recv()
+    let response = {
+        let runtime = state.runtime.lock().unwrap();
+        runtime.retry_recv()?
+    };
+
+    response_to_query(response)
+}
+
+/// Converts a [`Query::Prompt`] into the single-message [`LlmMessage::Prompt`]
+/// the worker understands: the first `system`-role message becomes
+/// [`LlmMessage::Prompt::system`], and the most recent non-system message
+/// becomes the prompt text.
+fn query_to_prompt(message: Query) -> Result<LlmMessage> {
+    match message {
+        Query::Prompt {
+            messages, config, ..
+        } => {
+            if let Some(tool_result) = find_tool_result(&messages) {
+                return tool_result;
+            }
+
+            let system = messages
+                .iter()
+                .find(|m| m.role == "system")
+                .and_then(|m| message_text(&m.content));
+
+            let message = messages
+                .iter()
+                .rev()
+                .find(|m| m.role != "system")
+                .and_then(|m| message_text(&m.content))
+                .ok_or_else(|| {
+                    Error::MessageEncodingError("Prompt has no user/assistant message".to_owned())
+                })?;
+
+            Ok(LlmMessage::Prompt {
+                system,
+                message,
+                num_samples: config.unwrap_or_default().generate_num_samples,
+                session_id: None,
+            })
+        }
+        Query::Exit => Ok(LlmMessage::Exit),
+        Query::Status => Ok(LlmMessage::Status),
+        other => Err(Error::MessageEncodingError(format!(
+            "Unsupported query variant for send_message: {other:?}"
+        ))),
+    }
+}
+
+fn message_text(content: &MessageContent) -> Option<String> {
+    match content {
+        MessageContent::Text(text) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+/// If the conversation's most recent non-system turn is a `"tool"`-role
+/// [`MessageContent::ToolResult`], resolves it to an
+/// [`LlmMessage::ToolResult`] by looking up the originating call's name from
+/// the preceding [`MessageContent::ToolCalls`] turn, so the worker can
+/// continue the same generation from its existing KV cache. Returns `None`
+/// when the turn isn't a tool result, so the caller falls through to
+/// building a regular prompt.
+fn find_tool_result(messages: &[QueryMessage]) -> Option<Result<LlmMessage>> {
+    let last = messages.iter().rev().find(|m| m.role != "system")?;
+    let MessageContent::ToolResult { call_id, content } = &last.content else {
+        return None;
+    };
+
+    let name = messages.iter().rev().find_map(|m| match &m.content {
+        MessageContent::ToolCalls(calls) => {
+            calls.iter().find(|c| &c.id == call_id).map(|c| c.name.clone())
+        }
+        _ => None,
+    });
+
+    Some(match name {
+        Some(name) => Ok(LlmMessage::ToolResult {
+            call_id: call_id.clone(),
+            name,
+            content: content.clone(),
+        }),
+        None => Err(Error::MessageEncodingError(format!(
+            "Tool result for call `{call_id}` has no matching tool call in the conversation"
+        ))),
+    })
+}
+
+/// Converts the worker's reply back into a [`Query::Response`] for the
+/// frontend.
+fn response_to_query(response: LlmMessage) -> Result<Query> {
+    match response {
+        LlmMessage::Response {
+            error,
+            message,
+            reasoning,
+        } => {
+            let mut messages = vec![QueryMessage {
+                role: "assistant".to_owned(),
+                content: MessageContent::Text(message),
+            }];
+            if let Some(reasoning) = reasoning {
+                messages.push(QueryMessage {
+                    role: "reasoning".to_owned(),
+                    content: MessageContent::Text(reasoning),
+                });
+            }
+            Ok(Query::Response {
+                error,
+                messages,
+                tools: vec![],
+            })
+        }
+        LlmMessage::Chunk(text) => Ok(Query::Response {
+            error: None,
+            messages: vec![QueryMessage {
+                role: "assistant".to_owned(),
+                content: MessageContent::Text(text),
+            }],
+            tools: vec![],
+        }),
+        LlmMessage::ToolCall { calls } => Ok(Query::Response {
+            error: None,
+            messages: vec![QueryMessage {
+                role: "assistant".to_owned(),
+                content: MessageContent::ToolCalls(calls),
+            }],
+            tools: vec![],
+        }),
+        _ => Err(Error::UnexpectedMessage),
+    }
+}
+
+/// Embeds `text` and stores it under `id` for later [`search_documents`] queries.
+#[command]
+pub(crate) async fn add_document(
+    state: State<'_, PluginState>,
+    id: String,
+    text: String,
+) -> Result<()> {
+    let embedding = embed_one(&state, text.clone())?;
+
+    let mut documents = state.documents.lock().unwrap();
+    documents.insert(id, text, embedding);
+
+    Ok(())
+}
+
+/// Embeds `query` and returns the `top_k` most similar documents previously
+/// stored via [`add_document`].
+#[command]
+pub(crate) async fn search_documents(
+    state: State<'_, PluginState>,
+    query: String,
+    top_k: usize,
+) -> Result<Vec<DocumentMatch>> {
+    let embedding = embed_one(&state, query)?;
+
+    let documents = state.documents.lock().unwrap();
+    Ok(documents
+        .search(&embedding, top_k)
+        .into_iter()
+        .map(|document| DocumentMatch {
+            id: document.id.clone(),
+            text: document.text.clone(),
+        })
+        .collect())
+}
+
+/// Reports the active model's device, name, and generation metrics.
+#[command]
+pub(crate) async fn runtime_status(state: State<'_, PluginState>) -> Result<RuntimeStatus> {
+    let response = {
+        let runtime = state.runtime.lock().unwrap();
+        runtime.status()?
+    };
+
+    match response {
+        LlmMessage::StatusReport {
+            device,
+            model_name,
+            last,
+            in_flight,
+        } => Ok(RuntimeStatus {
+            device,
+            model_name,
+            last,
+            in_flight,
+        }),
+        _ => Err(Error::UnexpectedMessage),
+    }
+}
+
+/// Embeds a single string via the runtime and returns its vector.
+fn embed_one(state: &State<'_, PluginState>, text: String) -> Result<Vec<f32>> {
+    let response = {
+        let runtime = state.runtime.lock().unwrap();
+        runtime.send(LlmMessage::Embed {
+            inputs: vec![text],
+            model: None,
+            normalize: true,
+        })?
+    };
+
+    match response {
+        LlmMessage::Embeddings { mut vectors } if !vectors.is_empty() => Ok(vectors.remove(0)),
+        _ => Err(Error::UnexpectedMessage),
+    }
 }
@@ -1,7 +1,7 @@
 use serde::de::DeserializeOwned;
-use tauri::{plugin::PluginApi, AppHandle, Runtime};
+use tauri::{plugin::PluginApi, AppHandle, Manager, Runtime};
 
-use crate::{models::*, LLMPluginConfig};
+use crate::{models::*, LLMPluginConfig, PluginState, SessionId};
 
 pub fn init<R: Runtime, C: DeserializeOwned>(
     app: &AppHandle<R>,
@@ -28,4 +28,22 @@ impl<R: Runtime> TauriPluginLlm<R> {
     // }
     // TODO: it probably makes sense to have send and try_recv here as well
     // for those that want to use the plugin in rust
+
+    /// Starts a new persistent chat session with its own KV cache.
+    ///
+    /// Prompts sent with this `session_id` only tokenize and forward their
+    /// new suffix, reusing the cache built up by earlier turns instead of
+    /// re-processing the whole conversation every time.
+    pub fn new_session(&self, session_id: SessionId) -> crate::Result<()> {
+        let state = self.handle.state::<PluginState>();
+        let runtime = state.runtime.lock().unwrap();
+        Ok(runtime.new_session(session_id)?)
+    }
+
+    /// Frees the KV cache associated with `session_id`.
+    pub fn drop_session(&self, session_id: SessionId) -> crate::Result<()> {
+        let state = self.handle.state::<PluginState>();
+        let runtime = state.runtime.lock().unwrap();
+        Ok(runtime.drop_session(session_id)?)
+    }
 }
@@ -14,12 +14,15 @@ pub use templates::*;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+use crate::llm::rag::DocumentStore;
 use crate::llm::runtime::LLMRuntime;
 #[cfg(desktop)]
 use desktop::TauriPluginLlm;
 pub use error::{Error, Result};
 pub use llm::loaders;
 pub use llm::runtime;
+pub use llm::runtime::LlmMessage;
+pub use llm::runtime::SessionId;
 #[cfg(mobile)]
 use mobile::TauriPluginLlm;
 pub use models::*;
@@ -56,6 +59,7 @@ pub struct Builder {
 
 pub struct PluginState {
     runtime: Arc<Mutex<LLMRuntime>>,
+    documents: Arc<Mutex<DocumentStore>>,
 }
 
 impl Builder {
@@ -74,7 +78,10 @@ impl Builder {
         PluginBuilder::<R, LLMPluginConfig>::new("llm")
             .invoke_handler(tauri::generate_handler![
                 commands::send_message,
-                commands::retry_recv
+                commands::retry_recv,
+                commands::add_document,
+                commands::search_documents,
+                commands::runtime_status
             ])
             .setup(|app, api| {
                 let config = self
@@ -92,6 +99,7 @@ impl Builder {
 
                     PluginState {
                         runtime: Arc::new(Mutex::new(runtime)),
+                        documents: Arc::new(Mutex::new(DocumentStore::new())),
                     }
                 });
 
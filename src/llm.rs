@@ -6,4 +6,6 @@
 
 pub mod llmconfig;
 pub mod loaders;
+pub mod metrics;
+pub mod rag;
 pub mod runtime;
@@ -32,6 +32,127 @@ pub struct LLMRuntimeConfig {
 
     /// Enables logging
     pub verbose: bool,
+
+    /// Which [`ValidTransformerBackend`] should service [`LlmMessage`](crate::runtime::LlmMessage)
+    /// traffic for this runtime.
+    pub backend: ValidTransformerBackend,
+
+    /// Enables retrieval-augmented context from a crawled workspace.
+    ///
+    /// When set, the runtime crawls [`RagConfig::root`] once at startup, embeds
+    /// the resulting chunks, and prepends the most similar ones as system
+    /// context to every prompt. See [`crate::llm::rag`].
+    pub rag: Option<RagConfig>,
+
+    /// Selects a pluggable context backend consulted before every prompt.
+    ///
+    /// The backend is built once when the worker starts and lives only on the
+    /// worker thread: [`MemoryBackendConfig::File`] loads its `.txt` files at
+    /// that point, and [`MemoryBackendConfig::Vector`] starts empty. The
+    /// runtime has no message or command that adds documents to it later.
+    /// See [`crate::runtime::MemoryBackend`].
+    pub memory: Option<MemoryBackendConfig>,
+
+    /// Hugging Face Hub repository to auto-download missing files from.
+    ///
+    /// When set, [`LLMRuntimeConfig::resolve_hub_paths`] downloads whichever
+    /// of `tokenizer_config_file`, `model_config_file`, `model_index_file` +
+    /// `model_dir`, or `model_file` are still `None` into the local Hub
+    /// cache and fills in the resulting paths. Any path already set in the
+    /// config is left untouched, so local files always take precedence.
+    pub repo: Option<HubRepo>,
+}
+
+/// A Hugging Face Hub repository [`LLMRuntimeConfig`] can resolve missing
+/// model files from. See [`LLMRuntimeConfig::resolve_hub_paths`].
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct HubRepo {
+    /// Repository id, e.g. `"Qwen/Qwen3-4B-Instruct-2507"`.
+    pub id: String,
+
+    /// Branch, tag, or commit sha to pin. Defaults to `"main"`.
+    pub revision: Option<String>,
+}
+
+/// Configures the [`crate::runtime::MemoryBackend`] consulted before every
+/// prompt to retrieve grounding context.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub enum MemoryBackendConfig {
+    /// Scores `.txt` files in `directory` by query/document keyword overlap.
+    ///
+    /// Needs no embeddings, so it works even for models that don't implement
+    /// [`crate::runtime::LLMRuntimeModel::embed`].
+    File {
+        directory: PathBuf,
+
+        /// Passages are added to the returned context until this budget
+        /// (estimated at ~4 characters per token) would be exceeded.
+        max_tokens: usize,
+    },
+
+    /// Retrieves the `top_k` most similar passages from an in-memory
+    /// cosine-similarity vector store, embedding both documents and queries
+    /// via the active model.
+    Vector {
+        top_k: usize,
+
+        /// Passages are added to the returned context until this budget
+        /// (estimated at ~4 characters per token) would be exceeded.
+        max_tokens: usize,
+    },
+}
+
+impl MemoryBackendConfig {
+    /// The configured token budget, regardless of which variant is selected.
+    pub fn max_tokens(&self) -> usize {
+        match self {
+            MemoryBackendConfig::File { max_tokens, .. } => *max_tokens,
+            MemoryBackendConfig::Vector { max_tokens, .. } => *max_tokens,
+        }
+    }
+}
+
+/// Configures the workspace crawl used for retrieval-augmented generation.
+///
+/// See [`crate::llm::rag::crawl_workspace`] and [`crate::llm::rag::VectorIndex`].
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RagConfig {
+    /// Directory to crawl. Walked recursively, honoring `.gitignore`.
+    pub root: PathBuf,
+
+    /// Only files whose extension (without the leading dot) is in this list are indexed.
+    pub extensions: Vec<String>,
+
+    /// Maximum number of characters per indexed chunk.
+    pub max_chunk_size: usize,
+
+    /// Number of most similar chunks to retrieve and prepend per prompt.
+    pub top_k: usize,
+}
+
+/// Selects the generation backend an [`LLMRuntime`](crate::runtime::LLMRuntime)
+/// dispatches to.
+///
+/// This lets a desktop app fall back to a hosted model when no local weights
+/// are present, or A/B a local model against a remote one, without the
+/// frontend changing any call sites: both variants are served behind the
+/// same `LLMRuntimeModel` trait.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub enum ValidTransformerBackend {
+    /// Run inference in-process against locally loaded candle weights.
+    Local,
+
+    /// Forward every request to a remote OpenAI-compatible `/chat/completions` endpoint.
+    OpenAiCompatible {
+        /// Base URL of the remote API, without the `/chat/completions` suffix.
+        endpoint: String,
+
+        /// Bearer token sent as `Authorization: Bearer <api_key>`, if required.
+        api_key: Option<String>,
+
+        /// Name of the remote model to request.
+        model: String,
+    },
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -58,6 +179,10 @@ pub struct ModelConfig {
     /// Repetition penalty
     pub penalty: f32,
 
+    /// Number of most recently generated tokens scanned when applying
+    /// [`Self::penalty`]. A typical value is `64`.
+    pub repeat_last_n: usize,
+
     /// Some models expect a generation seed.
     ///
     /// This can either be a fixed value or random where random is the default, if no explicit
@@ -69,6 +194,13 @@ pub struct ModelConfig {
 
     /// Enable streaming responses
     pub streaming: bool,
+
+    /// Maximum number of tool-call round trips allowed per [`Query::Prompt`](crate::Query::Prompt)
+    /// before the runtime gives up and returns whatever text the model produced.
+    ///
+    /// Each round trip is one `ToolCall` emitted by the model followed by a
+    /// `ToolResult` sent back by the caller.
+    pub max_tool_iterations: usize,
 }
 
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
@@ -91,6 +223,293 @@ pub enum ModelFileType {
     Pickle,
 }
 
+/// A partial, mergeable view of [`LLMRuntimeConfig`].
+///
+/// Every field is optional so a layer (defaults, a file, environment
+/// variables, explicit overrides) only needs to specify the keys it wants to
+/// set. See [`ConfigLoader`].
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct PartialLLMRuntimeConfig {
+    pub tokenizer_config_file: Option<PathBuf>,
+    pub model_config_file: Option<PathBuf>,
+    pub model_index_file: Option<PathBuf>,
+    pub model_file: Option<PathBuf>,
+    pub model_dir: Option<PathBuf>,
+    pub model_config: Option<PartialModelConfig>,
+    pub verbose: Option<bool>,
+    pub backend: Option<ValidTransformerBackend>,
+    pub rag: Option<RagConfig>,
+    pub memory: Option<MemoryBackendConfig>,
+    pub repo: Option<HubRepo>,
+}
+
+/// A partial, mergeable view of [`ModelConfig`]. See [`PartialLLMRuntimeConfig`].
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct PartialModelConfig {
+    pub top_k: Option<usize>,
+    pub top_p: Option<f32>,
+    pub temperature: Option<f32>,
+    pub name: Option<String>,
+    pub file_type: Option<ModelFileType>,
+    pub penalty: Option<f32>,
+    pub repeat_last_n: Option<usize>,
+    pub seed: Option<GenerationSeed>,
+    pub thinking: Option<bool>,
+    pub streaming: Option<bool>,
+    pub max_tool_iterations: Option<usize>,
+}
+
+impl PartialLLMRuntimeConfig {
+    /// Merges `other` on top of `self`: every field `other` sets wins, every
+    /// field it leaves `None` keeps whatever `self` already had.
+    fn merge(self, other: Self) -> Self {
+        Self {
+            tokenizer_config_file: other.tokenizer_config_file.or(self.tokenizer_config_file),
+            model_config_file: other.model_config_file.or(self.model_config_file),
+            model_index_file: other.model_index_file.or(self.model_index_file),
+            model_file: other.model_file.or(self.model_file),
+            model_dir: other.model_dir.or(self.model_dir),
+            model_config: match (self.model_config, other.model_config) {
+                (Some(base), Some(over)) => Some(base.merge(over)),
+                (base, over) => over.or(base),
+            },
+            verbose: other.verbose.or(self.verbose),
+            backend: other.backend.or(self.backend),
+            rag: other.rag.or(self.rag),
+            memory: other.memory.or(self.memory),
+            repo: other.repo.or(self.repo),
+        }
+    }
+}
+
+impl PartialModelConfig {
+    fn merge(self, other: Self) -> Self {
+        Self {
+            top_k: other.top_k.or(self.top_k),
+            top_p: other.top_p.or(self.top_p),
+            temperature: other.temperature.or(self.temperature),
+            name: other.name.or(self.name),
+            file_type: other.file_type.or(self.file_type),
+            penalty: other.penalty.or(self.penalty),
+            repeat_last_n: other.repeat_last_n.or(self.repeat_last_n),
+            seed: other.seed.or(self.seed),
+            thinking: other.thinking.or(self.thinking),
+            streaming: other.streaming.or(self.streaming),
+            max_tool_iterations: other.max_tool_iterations.or(self.max_tool_iterations),
+        }
+    }
+}
+
+/// Builds an [`LLMRuntimeConfig`] by merging layers in precedence order:
+/// built-in defaults, a config file, environment variables, then explicit
+/// programmatic overrides. Each later layer only replaces the keys it sets.
+///
+/// ```ignore
+/// let config = ConfigLoader::new()
+///     .with_defaults(defaults)
+///     .with_file("config.toml")?
+///     .with_env("LLM")
+///     .build()?;
+/// ```
+#[derive(Default)]
+pub struct ConfigLoader {
+    partial: PartialLLMRuntimeConfig,
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies built-in defaults as the lowest-precedence layer.
+    pub fn with_defaults(mut self, defaults: PartialLLMRuntimeConfig) -> Self {
+        self.partial = self.partial.merge(defaults);
+        self
+    }
+
+    /// Merges in a config file, auto-detecting `.json`/`.toml`/`.yaml`/`.yml`
+    /// from its extension.
+    pub fn with_file<P>(mut self, path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+
+        let layer: PartialLLMRuntimeConfig =
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => serde_json::from_str(&contents)
+                    .map_err(|e| Error::ExecutionError(e.to_string()))?,
+                Some("toml") => {
+                    toml::from_str(&contents).map_err(|e| Error::ExecutionError(e.to_string()))?
+                }
+                Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)
+                    .map_err(|e| Error::ExecutionError(e.to_string()))?,
+                other => {
+                    return Err(Error::ExecutionError(format!(
+                        "Unsupported config file extension: {other:?}"
+                    )))
+                }
+            };
+
+        self.partial = self.partial.merge(layer);
+        Ok(self)
+    }
+
+    /// Merges in environment variables under `prefix`, e.g.
+    /// `LLM_MODEL_CONFIG__TEMPERATURE=0.7` maps into
+    /// `ModelConfig.temperature` when `prefix` is `"LLM"`. A `__` separator
+    /// nests into `model_config`; everything else is treated as a top-level
+    /// field.
+    pub fn with_env(mut self, prefix: &str) -> Self {
+        self.partial = self.partial.merge(Self::env_layer(prefix));
+        self
+    }
+
+    /// Applies explicit programmatic overrides as the highest-precedence layer.
+    pub fn with_overrides(mut self, overrides: PartialLLMRuntimeConfig) -> Self {
+        self.partial = self.partial.merge(overrides);
+        self
+    }
+
+    /// Resolves the merged layers into a complete [`LLMRuntimeConfig`],
+    /// failing if a required field (`backend`, `model_config`, or one of
+    /// `model_config`'s `top_k`, `top_p`, `temperature`, `name`, `file_type`,
+    /// `penalty`) was never set.
+    pub fn build(self) -> Result<LLMRuntimeConfig, Error> {
+        let partial = self.partial;
+
+        let model_config = partial
+            .model_config
+            .ok_or_else(|| Error::MissingConfigLLM("model_config is missing".to_owned()))?;
+
+        Ok(LLMRuntimeConfig {
+            tokenizer_config_file: partial.tokenizer_config_file,
+            model_config_file: partial.model_config_file,
+            model_index_file: partial.model_index_file,
+            model_file: partial.model_file,
+            model_dir: partial.model_dir,
+            model_config: ModelConfig {
+                top_k: model_config.top_k.ok_or_else(|| {
+                    Error::MissingConfigLLM("model_config.top_k is missing".to_owned())
+                })?,
+                top_p: model_config.top_p.ok_or_else(|| {
+                    Error::MissingConfigLLM("model_config.top_p is missing".to_owned())
+                })?,
+                temperature: model_config.temperature.ok_or_else(|| {
+                    Error::MissingConfigLLM("model_config.temperature is missing".to_owned())
+                })?,
+                name: model_config.name.ok_or_else(|| {
+                    Error::MissingConfigLLM("model_config.name is missing".to_owned())
+                })?,
+                file_type: model_config.file_type.ok_or_else(|| {
+                    Error::MissingConfigLLM("model_config.file_type is missing".to_owned())
+                })?,
+                penalty: model_config.penalty.ok_or_else(|| {
+                    Error::MissingConfigLLM("model_config.penalty is missing".to_owned())
+                })?,
+                seed: model_config.seed.unwrap_or_default(),
+                thinking: model_config.thinking.unwrap_or(false),
+                streaming: model_config.streaming.unwrap_or(false),
+                max_tool_iterations: model_config.max_tool_iterations.unwrap_or(8),
+                repeat_last_n: model_config.repeat_last_n.unwrap_or(64),
+            },
+            verbose: partial.verbose.unwrap_or(false),
+            backend: partial
+                .backend
+                .ok_or_else(|| Error::MissingConfigLLM("backend is missing".to_owned()))?,
+            rag: partial.rag,
+            memory: partial.memory,
+            repo: partial.repo,
+        })
+    }
+
+    fn env_layer(prefix: &str) -> PartialLLMRuntimeConfig {
+        let mut partial = PartialLLMRuntimeConfig::default();
+        let env_prefix = format!("{prefix}_");
+
+        for (key, value) in std::env::vars() {
+            let Some(rest) = key.strip_prefix(&env_prefix) else {
+                continue;
+            };
+
+            match rest.split("__").collect::<Vec<_>>().as_slice() {
+                ["MODEL_CONFIG", field] => {
+                    let model_config = partial.model_config.get_or_insert_with(Default::default);
+                    Self::apply_model_config_env(model_config, field, &value, &key);
+                }
+                [field] => Self::apply_runtime_env(&mut partial, field, &value, &key),
+                _ => tracing::warn!("Unrecognized config env var: {key}"),
+            }
+        }
+
+        partial
+    }
+
+    fn apply_runtime_env(
+        partial: &mut PartialLLMRuntimeConfig,
+        field: &str,
+        value: &str,
+        key: &str,
+    ) {
+        match field {
+            "TOKENIZER_CONFIG_FILE" => partial.tokenizer_config_file = Some(PathBuf::from(value)),
+            "MODEL_CONFIG_FILE" => partial.model_config_file = Some(PathBuf::from(value)),
+            "MODEL_INDEX_FILE" => partial.model_index_file = Some(PathBuf::from(value)),
+            "MODEL_FILE" => partial.model_file = Some(PathBuf::from(value)),
+            "MODEL_DIR" => partial.model_dir = Some(PathBuf::from(value)),
+            "VERBOSE" => match value.parse() {
+                Ok(verbose) => partial.verbose = Some(verbose),
+                Err(e) => tracing::warn!("Invalid value for {key}: {e}"),
+            },
+            "REPO_ID" => {
+                let repo = partial.repo.get_or_insert_with(|| HubRepo {
+                    id: String::new(),
+                    revision: None,
+                });
+                repo.id = value.to_owned();
+            }
+            "REPO_REVISION" => {
+                let repo = partial.repo.get_or_insert_with(|| HubRepo {
+                    id: String::new(),
+                    revision: None,
+                });
+                repo.revision = Some(value.to_owned());
+            }
+            _ => tracing::warn!("Unrecognized config env var: {key}"),
+        }
+    }
+
+    fn apply_model_config_env(
+        config: &mut PartialModelConfig,
+        field: &str,
+        value: &str,
+        key: &str,
+    ) {
+        macro_rules! set_parsed {
+            ($target:expr) => {
+                match value.parse() {
+                    Ok(parsed) => $target = Some(parsed),
+                    Err(e) => tracing::warn!("Invalid value for {key}: {e}"),
+                }
+            };
+        }
+
+        match field {
+            "TOP_K" => set_parsed!(config.top_k),
+            "TOP_P" => set_parsed!(config.top_p),
+            "TEMPERATURE" => set_parsed!(config.temperature),
+            "NAME" => config.name = Some(value.to_string()),
+            "PENALTY" => set_parsed!(config.penalty),
+            "REPEAT_LAST_N" => set_parsed!(config.repeat_last_n),
+            "THINKING" => set_parsed!(config.thinking),
+            "STREAMING" => set_parsed!(config.streaming),
+            "MAX_TOOL_ITERATIONS" => set_parsed!(config.max_tool_iterations),
+            _ => tracing::warn!("Unrecognized config env var: {key}"),
+        }
+    }
+}
+
 impl LLMRuntimeConfig {
     ///Loads a config from path
     pub fn from_path<P>(path: P) -> Result<Self, Error>
@@ -100,4 +519,115 @@ impl LLMRuntimeConfig {
         let mut file = File::open(path.as_ref()).map_err(|_| Error::ExecutionError)?;
         serde_json::from_reader(&mut file).map_err(|_| Error::ExecutionError)
     }
+
+    /// Downloads whichever of `tokenizer_config_file`, `model_config_file`,
+    /// `model_index_file`/`model_dir`, or `model_file` are still unset, from
+    /// [`Self::repo`], and fills them in.
+    ///
+    /// No-op if `repo` is `None`. Paths already set in the config are never
+    /// overwritten, so this is safe to call unconditionally before `init`.
+    pub fn resolve_hub_paths(&mut self) -> Result<(), Error> {
+        let Some(repo) = self.repo.clone() else {
+            return Ok(());
+        };
+
+        let api = hf_hub::api::sync::ApiBuilder::new()
+            .build()
+            .map_err(|e| Error::ExecutionError(e.to_string()))?;
+
+        let api_repo = api.repo(hf_hub::Repo::with_revision(
+            repo.id,
+            hf_hub::RepoType::Model,
+            repo.revision.unwrap_or_else(|| "main".to_owned()),
+        ));
+
+        if self.tokenizer_config_file.is_none() {
+            self.tokenizer_config_file = Some(
+                api_repo
+                    .get("tokenizer.json")
+                    .map_err(|e| Error::ExecutionError(e.to_string()))?,
+            );
+        }
+
+        if self.model_config_file.is_none() {
+            self.model_config_file = Some(
+                api_repo
+                    .get("config.json")
+                    .map_err(|e| Error::ExecutionError(e.to_string()))?,
+            );
+        }
+
+        match self.model_config.file_type {
+            ModelFileType::GGUF => self.resolve_hub_gguf(&api_repo)?,
+            ModelFileType::Safetensors => self.resolve_hub_safetensors(&api_repo)?,
+            ModelFileType::Pickle => {}
+        }
+
+        Ok(())
+    }
+
+    fn resolve_hub_gguf(&mut self, api_repo: &hf_hub::api::sync::ApiRepo) -> Result<(), Error> {
+        if self.model_file.is_some() {
+            return Ok(());
+        }
+
+        let filename = Self::find_hub_sibling(api_repo, |name| name.ends_with(".gguf"))?;
+        self.model_file = Some(
+            api_repo
+                .get(&filename)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?,
+        );
+
+        Ok(())
+    }
+
+    fn resolve_hub_safetensors(
+        &mut self,
+        api_repo: &hf_hub::api::sync::ApiRepo,
+    ) -> Result<(), Error> {
+        if self.model_index_file.is_some() && self.model_dir.is_some() {
+            return Ok(());
+        }
+
+        let index_path = api_repo
+            .get("model.safetensors.index.json")
+            .map_err(|e| Error::ExecutionError(e.to_string()))?;
+
+        let index: serde_json::Value = serde_json::from_reader(File::open(&index_path)?)?;
+
+        let shards = index["weight_map"]
+            .as_object()
+            .ok_or_else(|| {
+                Error::ExecutionError("malformed model.safetensors.index.json: no weight_map".to_owned())
+            })?
+            .values()
+            .filter_map(|shard| shard.as_str())
+            .collect::<std::collections::BTreeSet<_>>();
+
+        for shard in shards {
+            api_repo
+                .get(shard)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+        }
+
+        self.model_index_file.get_or_insert(index_path.clone());
+        self.model_dir
+            .get_or_insert_with(|| index_path.parent().unwrap_or(&index_path).to_path_buf());
+
+        Ok(())
+    }
+
+    fn find_hub_sibling(
+        api_repo: &hf_hub::api::sync::ApiRepo,
+        predicate: impl Fn(&str) -> bool,
+    ) -> Result<String, Error> {
+        api_repo
+            .info()
+            .map_err(|e| Error::ExecutionError(e.to_string()))?
+            .siblings
+            .into_iter()
+            .map(|sibling| sibling.rfilename)
+            .find(|name| predicate(name))
+            .ok_or_else(|| Error::ExecutionError("no matching file found in Hub repo".to_owned()))
+    }
 }
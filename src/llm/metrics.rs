@@ -0,0 +1,111 @@
+//! Runtime generation metrics and backend version tracking.
+//!
+//! [`global`] is a process-wide registry so [`crate::runtime::LLMRuntime`]
+//! (prompt/generated token counters, time-to-first-token, KV-cache clears,
+//! and a per-variant "model version" gauge) can report into the same place
+//! without threading a handle through every call site. The runtime fills the
+//! counters; no loader calls [`Metrics::record_model_version`], so the version
+//! gauge stays empty.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Returns the process-wide [`Metrics`] registry, creating it on first access.
+pub fn global() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::default)
+}
+
+/// Counters/gauges tracked across every generation this process runs.
+#[derive(Default)]
+pub struct Metrics {
+    prompt_tokens: AtomicU64,
+    generated_tokens: AtomicU64,
+    time_to_first_token_ms: AtomicU64,
+    kv_cache_clears: AtomicU64,
+    model_versions: Mutex<HashMap<String, String>>,
+}
+
+impl Metrics {
+    /// Folds one completed generation's [`crate::GenerationMetrics`] into the
+    /// running totals.
+    pub fn record_generation(&self, metrics: &crate::GenerationMetrics) {
+        self.prompt_tokens
+            .fetch_add(metrics.prompt_tokens as u64, Ordering::Relaxed);
+        self.generated_tokens
+            .fetch_add(metrics.generated_tokens as u64, Ordering::Relaxed);
+        self.time_to_first_token_ms.store(
+            (metrics.prompt_eval_seconds * 1000.0) as u64,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Counts one KV cache being dropped or reset.
+    pub fn record_kv_cache_clear(&self) {
+        self.kv_cache_clears.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the loaded model's version for `backend_variant` (e.g.
+    /// `"Qwen"`, `"Llama3"`, `"Gemma3"`), replacing whatever was recorded the
+    /// last time that variant was loaded.
+    pub fn record_model_version(&self, backend_variant: &str, version: String) {
+        self.model_versions
+            .lock()
+            .unwrap()
+            .insert(backend_variant.to_string(), version);
+    }
+
+    /// Returns a point-in-time read of every counter/gauge.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            prompt_tokens: self.prompt_tokens.load(Ordering::Relaxed),
+            generated_tokens: self.generated_tokens.load(Ordering::Relaxed),
+            time_to_first_token_ms: self.time_to_first_token_ms.load(Ordering::Relaxed),
+            kv_cache_clears: self.kv_cache_clears.load(Ordering::Relaxed),
+            model_versions: self.model_versions.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// A point-in-time read of [`Metrics`], returned by
+/// [`crate::runtime::LLMRuntime::metrics_snapshot`].
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct MetricsSnapshot {
+    pub prompt_tokens: u64,
+    pub generated_tokens: u64,
+    pub time_to_first_token_ms: u64,
+    pub kv_cache_clears: u64,
+    pub model_versions: HashMap<String, String>,
+}
+
+impl MetricsSnapshot {
+    /// Renders this snapshot in a minimal Prometheus text-exposition format,
+    /// for apps that want to scrape it rather than poll the Tauri command.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        text.push_str(&format!("llm_prompt_tokens_total {}\n", self.prompt_tokens));
+        text.push_str(&format!(
+            "llm_generated_tokens_total {}\n",
+            self.generated_tokens
+        ));
+        text.push_str(&format!(
+            "llm_time_to_first_token_ms {}\n",
+            self.time_to_first_token_ms
+        ));
+        text.push_str(&format!(
+            "llm_kv_cache_clears_total {}\n",
+            self.kv_cache_clears
+        ));
+
+        for (backend, version) in &self.model_versions {
+            text.push_str(&format!(
+                "llm_model_version{{backend=\"{backend}\",version=\"{version}\"}} 1\n"
+            ));
+        }
+
+        text
+    }
+}
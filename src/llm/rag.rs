@@ -0,0 +1,218 @@
+//! Workspace file crawling for automatic RAG context injection.
+//!
+//! [`crawl_workspace`] walks a [`RagConfig`](crate::llmconfig::RagConfig) root,
+//! respecting `.gitignore`, and splits every matching file into chunks. The
+//! resulting chunks are embedded (via [`crate::runtime::LLMRuntimeModel::embed`])
+//! and stored in a [`VectorIndex`], which [`VectorIndex::search`] then queries
+//! for the top-k most similar chunks to prepend as context to a prompt.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use ignore::WalkBuilder;
+
+use crate::error::Error;
+use crate::iter::IntoIterChunks;
+use crate::llmconfig::RagConfig;
+
+/// A single chunk of crawled file content, together with its embedding.
+pub struct IndexedChunk {
+    pub path: PathBuf,
+    pub text: String,
+    pub embedding: Vec<f32>,
+}
+
+/// An in-memory top-k similarity index over [`IndexedChunk`]s.
+///
+/// Crawling is incremental: [`Self::is_stale`] lets a caller skip re-embedding
+/// files that haven't changed since their last indexing pass.
+#[derive(Default)]
+pub struct VectorIndex {
+    chunks: Vec<IndexedChunk>,
+    file_mtimes: HashMap<PathBuf, SystemTime>,
+}
+
+impl VectorIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if `path` hasn't been indexed yet, or was modified
+    /// after its last indexing pass.
+    pub fn is_stale(&self, path: &Path, modified: SystemTime) -> bool {
+        match self.file_mtimes.get(path) {
+            Some(indexed) => modified > *indexed,
+            None => true,
+        }
+    }
+
+    /// Replaces all chunks previously indexed for `path` with `chunks`.
+    pub fn upsert_file(
+        &mut self,
+        path: PathBuf,
+        modified: SystemTime,
+        chunks: Vec<(String, Vec<f32>)>,
+    ) {
+        self.chunks.retain(|chunk| chunk.path != path);
+        self.chunks
+            .extend(chunks.into_iter().map(|(text, embedding)| IndexedChunk {
+                path: path.clone(),
+                text,
+                embedding,
+            }));
+        self.file_mtimes.insert(path, modified);
+    }
+
+    /// Returns the `top_k` chunks whose embedding is most cosine-similar to `query_embedding`.
+    pub fn search(&self, query_embedding: &[f32], top_k: usize) -> Vec<&IndexedChunk> {
+        let mut scored: Vec<(f32, &IndexedChunk)> = self
+            .chunks
+            .iter()
+            .map(|chunk| (cosine_similarity(query_embedding, &chunk.embedding), chunk))
+            .collect();
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(top_k).map(|(_, chunk)| chunk).collect()
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// One crawled file: its path, last-modified time, and content split into chunks.
+pub struct CrawledFile {
+    pub path: PathBuf,
+    pub modified: SystemTime,
+    pub chunks: Vec<String>,
+}
+
+/// Walks `config.root`, respecting `.gitignore`, and splits every file whose
+/// extension is in `config.extensions` into `config.max_chunk_size`-character
+/// chunks.
+///
+/// Files that can't be read as UTF-8 (likely binary) are skipped rather than
+/// failing the whole crawl.
+pub fn crawl_workspace(config: &RagConfig) -> Result<Vec<CrawledFile>, Error> {
+    let mut files = Vec::new();
+
+    for entry in WalkBuilder::new(&config.root).build() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                tracing::warn!("Error walking RAG workspace: {}", error);
+                continue;
+            }
+        };
+
+        if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+            continue;
+        }
+
+        let path = entry.path();
+        let is_allowed = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| config.extensions.iter().any(|allowed| allowed == ext));
+
+        if !is_allowed {
+            continue;
+        }
+
+        let modified = match path.metadata().and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            Err(error) => {
+                tracing::warn!("Could not stat {}: {}", path.display(), error);
+                continue;
+            }
+        };
+
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) => {
+                tracing::debug!("Skipping non-UTF-8 file {}: {}", path.display(), error);
+                continue;
+            }
+        };
+
+        let chunks = content
+            .chars()
+            .chunks(config.max_chunk_size.max(1))
+            .map(|chunk| chunk.cloned().collect::<String>())
+            .collect();
+
+        files.push(CrawledFile {
+            path: path.to_path_buf(),
+            modified,
+            chunks,
+        });
+    }
+
+    Ok(files)
+}
+
+/// A single embedded document stored by [`DocumentStore`].
+pub struct Document {
+    pub id: String,
+    pub text: String,
+    pub embedding: Vec<f32>,
+}
+
+/// A simple in-memory vector store for user-provided documents.
+///
+/// Distinct from [`VectorIndex`], which indexes chunks crawled from a
+/// workspace directory: this store holds whatever `(id, text)` pairs the app
+/// embeds directly (e.g. via the `add_document`/`search_documents` Tauri
+/// commands), answering top-k cosine-similarity queries the same way.
+#[derive(Default)]
+pub struct DocumentStore {
+    documents: Vec<Document>,
+}
+
+impl DocumentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `text`/`embedding` under `id`, replacing any document
+    /// previously stored under the same id.
+    pub fn insert(&mut self, id: String, text: String, embedding: Vec<f32>) {
+        self.documents.retain(|document| document.id != id);
+        self.documents.push(Document { id, text, embedding });
+    }
+
+    /// Removes the document stored under `id`, if any.
+    pub fn remove(&mut self, id: &str) {
+        self.documents.retain(|document| document.id != id);
+    }
+
+    /// Returns the `top_k` documents whose embedding is most cosine-similar to `query_embedding`.
+    pub fn search(&self, query_embedding: &[f32], top_k: usize) -> Vec<&Document> {
+        let mut scored: Vec<(f32, &Document)> = self
+            .documents
+            .iter()
+            .map(|document| (cosine_similarity(query_embedding, &document.embedding), document))
+            .collect();
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(top_k).map(|(_, document)| document).collect()
+    }
+}
+
+/// Formats retrieved chunks as a system-context block to prepend to a prompt.
+pub fn format_context(chunks: &[&IndexedChunk]) -> String {
+    let mut context = String::from("Relevant context from the workspace:\n");
+    for chunk in chunks {
+        context.push_str(&format!("\n--- {} ---\n{}\n", chunk.path.display(), chunk.text));
+    }
+    context
+}
@@ -1,9 +1,23 @@
 //! LLM Inference
+mod llama3;
+mod memory;
+mod openai;
 mod qwen3;
+mod token_stream;
+mod tool_call;
 
 use crate::error::Error;
 use crate::LlmMessage;
-use crate::{llm::llmconfig::LLMRuntimeConfig, llmconfig::ModelConfig, runtime::qwen3::Qwen3Model};
+use crate::ToolCall;
+use crate::{
+    llm::llmconfig::{LLMRuntimeConfig, MemoryBackendConfig, ValidTransformerBackend},
+    llm::rag::{self, VectorIndex},
+    llmconfig::ModelConfig,
+    runtime::llama3::LLama3Model,
+    runtime::memory::{FileMemoryBackend, VectorMemoryBackend},
+    runtime::openai::OpenAiBackend,
+    runtime::qwen3::Qwen3Model,
+};
 use candle_core::Device;
 use serde::Deserialize;
 use std::sync::mpsc::{Receiver, Sender};
@@ -11,7 +25,6 @@ use tokenizers::Tokenizer;
 use tracing::trace;
 
 use anyhow::{Error as E, Result};
-use candle_core::{quantized::gguf_file, DType, Tensor};
 use candle_nn::VarBuilder;
 
 /// This needs to be adapted
@@ -26,6 +39,83 @@ use tracing_subscriber::{filter, layer::SubscriberExt, util::SubscriberInitExt,
 
 const CHANNEL_BUFFER_SIZE: usize = 10;
 
+/// Identifies a persistent multi-turn chat session.
+///
+/// Prompts sent with the same [`SessionId`] reuse the KV cache built up by
+/// earlier turns in that session instead of re-processing the whole
+/// conversation from scratch. See [`LLMRuntime::new_session`].
+pub type SessionId = String;
+
+/// Messages exchanged between [`LLMRuntime`] and the model running on the worker thread.
+#[derive(Debug, Clone)]
+pub enum LlmMessage {
+    Prompt {
+        system: Option<String>,
+        message: String,
+        num_samples: usize,
+
+        /// When set, the model reuses the KV cache of this session instead
+        /// of starting a fresh one, only tokenizing and forwarding `message`.
+        session_id: Option<SessionId>,
+    },
+    /// Starts a new persistent chat session with its own KV cache.
+    NewSession { session_id: SessionId },
+    /// Frees the KV cache associated with a session.
+    DropSession { session_id: SessionId },
+    /// A partial, decoded text fragment produced while sampling.
+    ///
+    /// A model emits zero or more `Chunk` messages over `chunk_tx` before returning
+    /// its final [`LlmMessage::Response`] from [`LLMRuntimeModel::execute`].
+    Chunk(String),
+    Response {
+        error: Option<String>,
+        message: String,
+
+        /// Chain-of-thought extracted from a `<think>...</think>` span, if
+        /// the model produced one and thinking mode wasn't disabled.
+        reasoning: Option<String>,
+    },
+    /// Requests dense vector embeddings for each of `inputs`.
+    Embed {
+        inputs: Vec<String>,
+        model: Option<String>,
+        normalize: bool,
+    },
+    /// One embedding vector per input string, in request order.
+    Embeddings {
+        vectors: Vec<Vec<f32>>,
+    },
+    /// One or more tool/function calls the model wants the caller to run.
+    ///
+    /// The caller is expected to execute each call and send its result back
+    /// as a [`LlmMessage::ToolResult`] to continue the same generation from
+    /// the model's existing KV cache, rather than starting a new prompt.
+    ToolCall {
+        calls: Vec<ToolCall>,
+    },
+    /// The result of running a tool call previously emitted as
+    /// [`LlmMessage::ToolCall`].
+    ToolResult {
+        call_id: String,
+        name: String,
+        content: String,
+    },
+    Exit,
+    /// Requests the runtime's current device/model info and generation metrics.
+    Status,
+    /// Answers [`LlmMessage::Status`].
+    StatusReport {
+        device: String,
+        model_name: String,
+
+        /// Metrics for the most recently completed generation, if any.
+        last: Option<crate::GenerationMetrics>,
+
+        /// A snapshot of the generation currently in progress, if any.
+        in_flight: Option<crate::GenerationMetrics>,
+    },
+}
+
 pub struct LLMRuntime {
     model: Option<Box<dyn LLMRuntimeModel>>,
     config: LLMRuntimeConfig,
@@ -38,7 +128,15 @@ pub struct LLMRuntime {
 
 pub trait LLMRuntimeModel: Send + Sync {
     /// Sends a [`LlmMessage`] to the loaded model and start sampling
-    fn execute(&mut self, message: LlmMessage) -> Result<LlmMessage, Error>;
+    ///
+    /// `chunk_tx` lets the model emit [`LlmMessage::Chunk`] fragments as they are
+    /// decoded. The final, complete text is still returned as [`LlmMessage::Response`],
+    /// unless the model requested tool calls, which are returned as [`LlmMessage::ToolCall`].
+    fn execute(
+        &mut self,
+        message: LlmMessage,
+        chunk_tx: &Sender<LlmMessage>,
+    ) -> Result<LlmMessage, Error>;
 
     /// Initializes the model
     ///
@@ -47,6 +145,146 @@ pub trait LLMRuntimeModel: Send + Sync {
 
     /// Apply a chat template
     fn apply_chat_template(&mut self, template: String);
+
+    /// Computes a dense embedding vector for each input string.
+    ///
+    /// Models built on top of an encoder/decoder that exposes hidden states can
+    /// override this to mean-pool (and optionally L2-normalize) the last hidden
+    /// layer. The default implementation reports that this model has no
+    /// embedding support.
+    fn embed(&mut self, _inputs: Vec<String>) -> Result<Vec<Vec<f32>>, Error> {
+        Err(Error::ExecutionError(
+            "This model does not support embeddings".to_string(),
+        ))
+    }
+
+    /// Starts a new persistent chat session identified by `session_id`.
+    ///
+    /// The default implementation is a no-op for models that don't support
+    /// persistent sessions.
+    fn new_session(&mut self, _session_id: SessionId) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Frees the KV cache (if any) associated with `session_id`.
+    ///
+    /// The default implementation is a no-op for models that don't support
+    /// persistent sessions.
+    fn drop_session(&mut self, _session_id: SessionId) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Answers a [`LlmMessage::Status`] query with device/model info and
+    /// generation metrics.
+    ///
+    /// The default implementation reports no metrics, for backends that
+    /// don't track timing.
+    fn status(&self) -> LlmMessage {
+        LlmMessage::StatusReport {
+            device: String::new(),
+            model_name: String::new(),
+            last: None,
+            in_flight: None,
+        }
+    }
+}
+
+/// A pluggable generation backend.
+///
+/// Implemented by both in-process candle models and remote HTTP APIs so
+/// [`LLMRuntime`] can dispatch the same [`LlmMessage`] traffic to either,
+/// selected via [`ValidTransformerBackend`].
+pub trait TransformBackend: Send + Sync {
+    /// Completes `prompt` as raw text, with no chat template applied.
+    fn do_completion(&mut self, prompt: &str, num_samples: usize) -> Result<String, Error>;
+
+    /// Generates a chat-style response for `prompt`.
+    ///
+    /// The default implementation just forwards to [`Self::do_completion`];
+    /// backends with a real chat endpoint should override this.
+    fn do_generate(&mut self, prompt: &str, num_samples: usize) -> Result<String, Error> {
+        self.do_completion(prompt, num_samples)
+    }
+
+    /// Same as [`Self::do_generate`], but streams decoded fragments over
+    /// `chunk_tx` as they become available instead of only returning the
+    /// final text.
+    ///
+    /// The default implementation has no way to stream incrementally, so it
+    /// emits the full response as a single chunk.
+    fn do_generate_stream(
+        &mut self,
+        prompt: &str,
+        num_samples: usize,
+        chunk_tx: &Sender<LlmMessage>,
+    ) -> Result<String, Error> {
+        let text = self.do_generate(prompt, num_samples)?;
+        if let Err(error) = chunk_tx.send(LlmMessage::Chunk(text.clone())) {
+            tracing::error!("Error sending streamed chunk: {}", error);
+        }
+        Ok(text)
+    }
+}
+
+/// A pluggable source of grounding context consulted before every prompt.
+///
+/// Selected via [`crate::llmconfig::MemoryBackendConfig`]. Implementations
+/// decide for themselves whether retrieval needs embeddings (and so takes a
+/// `model` argument to call [`LLMRuntimeModel::embed`]) or can work from text
+/// alone.
+pub trait MemoryBackend: Send + Sync {
+    /// Adds `text`, identified by `id`, to the backend's store.
+    fn add_document(
+        &mut self,
+        id: String,
+        text: String,
+        model: &mut dyn LLMRuntimeModel,
+    ) -> Result<(), Error>;
+
+    /// Returns passages relevant to `query`, stopping once their combined
+    /// length would exceed `max_tokens` (an implementation-defined estimate).
+    fn get_context(
+        &mut self,
+        query: &str,
+        max_tokens: usize,
+        model: &mut dyn LLMRuntimeModel,
+    ) -> Result<Vec<String>, Error>;
+}
+
+/// A device preference for [`select_device`]. `Auto` tries CUDA, then
+/// Metal, then falls back to CPU.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum DevicePreference {
+    Cuda,
+    Metal,
+    Cpu,
+    #[default]
+    Auto,
+}
+
+/// Selects a [`Device`] honoring `preference`, or auto-detecting by trying
+/// CUDA, then Metal, then falling back to CPU. Returns the device alongside
+/// a human-readable label the caller can surface so the frontend can show
+/// whether inference is running accelerated or on CPU.
+pub fn select_device(preference: DevicePreference) -> Result<(Device, &'static str), Error> {
+    match preference {
+        DevicePreference::Cuda => Device::new_cuda(0)
+            .map(|device| (device, "CUDA"))
+            .map_err(|e| Error::ExecutionError(e.to_string())),
+        DevicePreference::Metal => Device::new_metal(0)
+            .map(|device| (device, "Metal"))
+            .map_err(|e| Error::ExecutionError(e.to_string())),
+        DevicePreference::Cpu => Ok((Device::Cpu, "CPU")),
+        DevicePreference::Auto => {
+            if let Ok(device) = Device::new_cuda(0) {
+                Ok((device, "CUDA"))
+            } else if let Ok(device) = Device::new_metal(0) {
+                Ok((device, "Metal"))
+            } else {
+                Ok((Device::Cpu, "CPU"))
+            }
+        }
+    }
 }
 
 impl Drop for LLMRuntime {
@@ -59,20 +297,21 @@ impl Drop for LLMRuntime {
 
 impl LLMRuntime {
     /// Creates a new LLM
-    pub fn from_config(config: LLMRuntimeConfig) -> Result<Self, Error> {
+    pub fn from_config(mut config: LLMRuntimeConfig) -> Result<Self, Error> {
         if config.verbose {
             let verbose = tracing_subscriber::fmt::layer().with_filter(filter::LevelFilter::DEBUG);
             Registry::default().with(verbose).init();
         }
+        config.resolve_hub_paths()?;
         let device = Self::load_default_device();
-        let model = Self::detect_model(&config.clone(), device)?;
+        let model: Box<dyn LLMRuntimeModel> = Self::detect_model(&config.clone(), device)?;
 
         let (ctrl_tx, ctrl_rx) = std::sync::mpsc::channel();
         let (response_tx, response_rx) = std::sync::mpsc::channel();
         let (exit_tx, exit_rx) = std::sync::mpsc::channel();
 
         Ok(Self {
-            model: Some(Box::new(model)),
+            model: Some(model),
             config,
 
             worker: None,
@@ -85,7 +324,16 @@ impl LLMRuntime {
     fn detect_model(
         config: &LLMRuntimeConfig,
         device: Device,
-    ) -> Result<impl LLMRuntimeModel, Error> {
+    ) -> Result<Box<dyn LLMRuntimeModel>, Error> {
+        if let ValidTransformerBackend::OpenAiCompatible {
+            endpoint,
+            api_key,
+            model,
+        } = config.backend.clone()
+        {
+            return Ok(Box::new(OpenAiBackend::new(endpoint, api_key, model)));
+        }
+
         let LLMRuntimeConfig { model_config, .. } = config.clone();
 
         let ModelConfig {
@@ -95,11 +343,33 @@ impl LLMRuntime {
             name,
             thinking,
             streaming,
+            penalty,
+            repeat_last_n,
+            max_tool_iterations,
             ..
         } = model_config;
 
+        let device_name = Self::device_label(&device);
+
         match &name {
-            _ if name.contains("Qwen3") => Ok(Qwen3Model {
+            _ if name.contains("Qwen3") => Ok(Box::new(Qwen3Model {
+                streaming,
+                device: Some(device),
+                tokenizer: None,
+                top_k,
+                top_p,
+                temperature,
+                thinking,
+                weights: None,
+                logits_processor: None,
+                penalty,
+                repeat_last_n,
+                device_name,
+                model_name: name,
+                last_metrics: None,
+                in_flight_metrics: None,
+            })),
+            _ if name.contains("Llama") => Ok(Box::new(LLama3Model {
                 streaming,
                 device: Some(device),
                 tokenizer: None,
@@ -109,40 +379,205 @@ impl LLMRuntime {
                 thinking,
                 weights: None,
                 logits_processor: None,
-            }),
+                cache: None,
+                penalty,
+                repeat_last_n,
+                next_position: 0,
+                num_samples: 0,
+                tool_iterations: 0,
+                seen_tool_calls: std::collections::HashSet::new(),
+                max_tool_iterations,
+                model_cfg: None,
+                sessions: std::collections::HashMap::new(),
+                active_session: None,
+                device_name,
+                model_name: name,
+                last_metrics: None,
+                in_flight_metrics: None,
+            })),
             _ => Err(Error::ExecutionError("".to_string())),
         }
     }
 
-    /// Loads the best default device that can be detected
+    /// A human-readable label for `device`, reported in [`LlmMessage::StatusReport`].
+    fn device_label(device: &Device) -> String {
+        match device {
+            Device::Cpu => "CPU".to_string(),
+            Device::Cuda(_) => "CUDA".to_string(),
+            Device::Metal(_) => "Metal".to_string(),
+        }
+    }
+
+    /// Loads the best default device that can be detected, trying CUDA, then
+    /// Metal, then falling back to CPU.
     fn load_default_device() -> Device {
-        if cfg!(target_os = "macos") {
-            match Device::new_metal(0) {
-                Ok(device) => {
-                    tracing::debug!("Select Metal Device (0)");
-                    device
+        // `select_device` with `Auto` always succeeds (it falls back to
+        // `Device::Cpu`), so the error branch is unreachable in practice.
+        match select_device(DevicePreference::Auto) {
+            Ok((device, label)) => {
+                tracing::debug!("Selected {label} device");
+                device
+            }
+            Err(error) => {
+                tracing::error!("Could not select a device, falling back to CPU: {}", error);
+                Device::Cpu
+            }
+        }
+    }
+
+    /// Crawls `rag_config.root` and embeds every chunk into a fresh [`VectorIndex`].
+    ///
+    /// Errors crawling or embedding an individual file are logged and that
+    /// file is skipped, rather than failing the whole index.
+    fn build_rag_index(
+        rag_config: &crate::llmconfig::RagConfig,
+        model: &mut dyn LLMRuntimeModel,
+    ) -> VectorIndex {
+        let mut index = VectorIndex::new();
+
+        let files = match rag::crawl_workspace(rag_config) {
+            Ok(files) => files,
+            Err(error) => {
+                tracing::error!("Error crawling RAG workspace: {}", error);
+                return index;
+            }
+        };
+
+        for file in files {
+            if !index.is_stale(&file.path, file.modified) {
+                continue;
+            }
+
+            match model.embed(file.chunks.clone()) {
+                Ok(embeddings) => {
+                    let chunks = file.chunks.into_iter().zip(embeddings).collect();
+                    index.upsert_file(file.path, file.modified, chunks);
                 }
                 Err(error) => {
-                    tracing::error!("Could not detect Metal device. Fall back to CPU: {}", error);
-                    Device::Cpu
+                    tracing::error!(
+                        "Error embedding RAG chunks for {}: {}",
+                        file.path.display(),
+                        error
+                    );
                 }
             }
-        } else if cfg!(not(target_os = "macos")) {
-            match Device::new_cuda(0) {
-                Ok(device) => {
-                    tracing::debug!("Select Cuda Device (0)");
-                    device
-                }
+        }
+
+        index
+    }
+
+    /// Retrieves the `top_k` chunks most similar to `message` and prepends
+    /// them to `system` as context, if a [`VectorIndex`] is available.
+    fn retrieve_rag_context(
+        rag_index: Option<&VectorIndex>,
+        rag_config: Option<&crate::llmconfig::RagConfig>,
+        model: &mut dyn LLMRuntimeModel,
+        message: &str,
+        system: Option<String>,
+    ) -> Option<String> {
+        let (index, rag_config) = match (rag_index, rag_config) {
+            (Some(index), Some(rag_config)) => (index, rag_config),
+            _ => return system,
+        };
+
+        let mut embeddings = match model.embed(vec![message.to_string()]) {
+            Ok(embeddings) => embeddings,
+            Err(error) => {
+                tracing::error!("Error embedding prompt for RAG retrieval: {}", error);
+                return system;
+            }
+        };
+
+        if embeddings.is_empty() {
+            return system;
+        }
+
+        let retrieved = index.search(&embeddings.remove(0), rag_config.top_k);
+        if retrieved.is_empty() {
+            return system;
+        }
+
+        let context = rag::format_context(&retrieved);
+        Some(match system {
+            Some(system) => format!("{context}\n{system}"),
+            None => context,
+        })
+    }
+
+    /// Builds the [`MemoryBackend`] selected by `memory_config`.
+    ///
+    /// A [`MemoryBackendConfig::File`] whose directory can't be read falls
+    /// back to an empty store rather than failing model initialization.
+    fn build_memory_backend(memory_config: &MemoryBackendConfig) -> Box<dyn MemoryBackend> {
+        match memory_config {
+            MemoryBackendConfig::File { directory, .. } => match FileMemoryBackend::new(directory)
+            {
+                Ok(backend) => Box::new(backend),
                 Err(error) => {
-                    tracing::error!("Could not detect Cuda device. Fall back to CPU: {}", error);
-                    Device::Cpu
+                    tracing::error!(
+                        "Error loading file-backed memory store from {}: {}",
+                        directory.display(),
+                        error
+                    );
+                    Box::new(FileMemoryBackend::empty())
                 }
+            },
+            MemoryBackendConfig::Vector { top_k, .. } => {
+                Box::new(VectorMemoryBackend::new(*top_k))
             }
-        } else {
-            Device::Cpu
         }
     }
 
+    /// Retrieves context from `memory_backend` (if configured) and prepends
+    /// it to `system`.
+    fn retrieve_memory_context(
+        memory_backend: Option<&mut Box<dyn MemoryBackend>>,
+        max_tokens: usize,
+        model: &mut dyn LLMRuntimeModel,
+        message: &str,
+        system: Option<String>,
+    ) -> Option<String> {
+        let memory_backend = match memory_backend {
+            Some(memory_backend) => memory_backend,
+            None => return system,
+        };
+
+        match memory_backend.get_context(message, max_tokens, model) {
+            Ok(passages) if !passages.is_empty() => {
+                let context = format!("Relevant context:\n\n{}", passages.join("\n\n"));
+                Some(match system {
+                    Some(system) => format!("{context}\n{system}"),
+                    None => context,
+                })
+            }
+            Ok(_) => system,
+            Err(error) => {
+                tracing::error!("Error retrieving memory context: {}", error);
+                system
+            }
+        }
+    }
+
+    /// Folds `model`'s latest [`crate::GenerationMetrics`] into the process
+    /// metrics registry after `result` completed successfully, then returns
+    /// `result` unchanged.
+    fn record_generation_metrics(
+        result: Result<LlmMessage, Error>,
+        model: &dyn LLMRuntimeModel,
+    ) -> Result<LlmMessage, Error> {
+        if result.is_ok() {
+            if let LlmMessage::StatusReport {
+                last: Some(metrics),
+                ..
+            } = model.status()
+            {
+                crate::llm::metrics::global().record_generation(&metrics);
+            }
+        }
+
+        result
+    }
+
     /// ## Description
     ///
     /// Executes the LLM and returns a [`Sender`] to interact with the Model.
@@ -169,15 +604,79 @@ impl LLMRuntime {
                 return;
             }
 
+            let rag_index = config.rag.as_ref().map(|rag_config| {
+                Self::build_rag_index(rag_config, model.as_mut())
+            });
+
+            let mut memory_backend = config.memory.as_ref().map(Self::build_memory_backend);
+
             loop {
                 if let Ok(message) = control_rx.try_recv() {
                     tracing::debug!("Sending message to model");
 
                     let model_response_message = match message {
-                        LlmMessage::Prompt { .. } => model.execute(message),
+                        LlmMessage::Prompt {
+                            system,
+                            message,
+                            num_samples,
+                            session_id,
+                        } => {
+                            let system = Self::retrieve_rag_context(
+                                rag_index.as_ref(),
+                                config.rag.as_ref(),
+                                model.as_mut(),
+                                &message,
+                                system,
+                            );
+
+                            let system = Self::retrieve_memory_context(
+                                memory_backend.as_mut(),
+                                config.memory.as_ref().map(MemoryBackendConfig::max_tokens).unwrap_or(0),
+                                model.as_mut(),
+                                &message,
+                                system,
+                            );
+
+                            let result = model.execute(
+                                LlmMessage::Prompt {
+                                    system,
+                                    message,
+                                    num_samples,
+                                    session_id,
+                                },
+                                &response_tx,
+                            );
+                            Self::record_generation_metrics(result, model.as_ref())
+                        }
+                        LlmMessage::ToolResult { .. } => {
+                            let result = model.execute(message, &response_tx);
+                            Self::record_generation_metrics(result, model.as_ref())
+                        }
+                        LlmMessage::NewSession { session_id } => {
+                            model.new_session(session_id).map(|_| LlmMessage::Response {
+                                error: None,
+                                message: String::new(),
+                                reasoning: None,
+                            })
+                        }
+                        LlmMessage::DropSession { session_id } => {
+                            crate::llm::metrics::global().record_kv_cache_clear();
+                            model.drop_session(session_id).map(|_| LlmMessage::Response {
+                                error: None,
+                                message: String::new(),
+                                reasoning: None,
+                            })
+                        }
                         LlmMessage::Exit => break,
                         LlmMessage::Response { .. } => Err(Error::UnexpectedMessage),
-                        LlmMessage::Status => Err(Error::UnexpectedMessage),
+                        LlmMessage::Chunk(_) => Err(Error::UnexpectedMessage),
+                        LlmMessage::ToolCall { .. } => Err(Error::UnexpectedMessage),
+                        LlmMessage::Embed { inputs, .. } => model
+                            .embed(inputs)
+                            .map(|vectors| LlmMessage::Embeddings { vectors }),
+                        LlmMessage::Embeddings { .. } => Err(Error::UnexpectedMessage),
+                        LlmMessage::Status => Ok(model.status()),
+                        LlmMessage::StatusReport { .. } => Err(Error::UnexpectedMessage),
                     };
 
                     match model_response_message {
@@ -210,6 +709,35 @@ impl LLMRuntime {
         Ok(self.response.1.try_recv()?)
     }
 
+    /// Starts a new persistent chat session with its own KV cache.
+    ///
+    /// Subsequent `Prompt`s sent with this [`SessionId`] only tokenize and
+    /// forward their new suffix, reusing the cache built up by earlier turns.
+    pub fn new_session(&self, session_id: SessionId) -> Result<(), Error> {
+        self.send(LlmMessage::NewSession { session_id })?;
+        Ok(())
+    }
+
+    /// Frees the KV cache associated with `session_id`.
+    pub fn drop_session(&self, session_id: SessionId) -> Result<(), Error> {
+        self.send(LlmMessage::DropSession { session_id })?;
+        Ok(())
+    }
+
+    /// Queries the active model for its device/model info and generation metrics.
+    pub fn status(&self) -> Result<LlmMessage, Error> {
+        self.send(LlmMessage::Status)
+    }
+
+    /// Returns the current process-wide [`MetricsSnapshot`](crate::llm::metrics::MetricsSnapshot):
+    /// cumulative prompt/generated token counts, time-to-first-token, KV-cache
+    /// clears, and whatever model versions were passed to
+    /// [`Metrics::record_model_version`](crate::llm::metrics::Metrics::record_model_version)
+    /// (no backend records one while loading).
+    pub fn metrics_snapshot(&self) -> crate::llm::metrics::MetricsSnapshot {
+        crate::llm::metrics::global().snapshot()
+    }
+
     pub fn shutdown(&self) {
         self.exit
             .0
@@ -1,9 +1,13 @@
+use std::collections::HashMap;
 use std::fs::File;
+use std::sync::mpsc::Sender;
 
 use crate::error::Error;
 use crate::llmconfig::{LLMRuntimeConfig, ModelConfig};
 use crate::loaders::safetensor::IndexFile;
-use crate::runtime::{LLMRuntimeModel, LlmMessage};
+use crate::runtime::token_stream::TokenOutputStream;
+use crate::runtime::tool_call::{LlamaToolCallParser, ToolCallParseState, ToolCallParser};
+use crate::runtime::{LLMRuntimeModel, LlmMessage, SessionId};
 use candle_core::Device;
 use candle_core::Tensor;
 use candle_nn::VarBuilder;
@@ -16,6 +20,13 @@ use candle_transformers::generation::{LogitsProcessor, Sampling};
 use rand::Rng;
 use tokenizers::Tokenizer;
 
+/// A persistent chat session's KV cache, plus how many tokens it has
+/// already committed to that cache.
+struct SessionState {
+    cache: model::Cache,
+    committed_tokens: usize,
+}
+
 pub struct LLama3Model {
     pub(crate) streaming: bool,
     pub(crate) device: Option<Device>,
@@ -28,96 +39,320 @@ pub struct LLama3Model {
     pub(crate) logits_processor: Option<LogitsProcessor>,
     pub(crate) cache: Option<model::Cache>,
     pub(crate) penalty: f32,
+
+    /// Number of most recently generated tokens scanned when applying
+    /// [`Self::penalty`].
+    pub(crate) repeat_last_n: usize,
+
+    /// KV-cache position the next forward pass should start writing at.
+    ///
+    /// Reset to `0` on a new [`LlmMessage::Prompt`] that has no `session_id`
+    /// (a session tracks its own position). A [`LlmMessage::ToolResult`]
+    /// only encodes its own turn and resumes from here, so the cache never has
+    /// to be rebuilt from scratch for a tool round trip.
+    pub(crate) next_position: usize,
+
+    /// `num_samples` of the [`LlmMessage::Prompt`] that started the current
+    /// tool-calling round. Reused for every [`LlmMessage::ToolResult`]
+    /// continuation, since those messages don't carry one of their own.
+    pub(crate) num_samples: usize,
+
+    /// Number of tool calls emitted so far for the current prompt.
+    pub(crate) tool_iterations: usize,
+
+    /// `(name, arguments)` pairs already emitted during the current
+    /// tool-calling round, so a model re-issuing an identical call can be
+    /// caught instead of looping forever. Reset on a new [`LlmMessage::Prompt`].
+    pub(crate) seen_tool_calls: std::collections::HashSet<String>,
+
+    /// Caps [`Self::tool_iterations`] before the runtime gives up and returns
+    /// the model's raw text instead of another [`LlmMessage::ToolCall`].
+    pub(crate) max_tool_iterations: usize,
+
+    /// Config needed to build a fresh [`model::Cache`] for a new session.
+    /// Captured once in [`Self::init`].
+    pub(crate) model_cfg: Option<model::Config>,
+
+    /// Per-session KV caches, keyed by [`SessionId`]. A prompt sent with a
+    /// `session_id` only tokenizes and forwards its new suffix, reusing the
+    /// cached keys/values from earlier turns instead of re-processing the
+    /// whole conversation every time.
+    pub(crate) sessions: HashMap<SessionId, SessionState>,
+
+    /// The session the current tool-calling round (if any) is running
+    /// against. Set from [`LlmMessage::Prompt::session_id`] and consulted by
+    /// any follow-up [`LlmMessage::ToolResult`], which carries no session of
+    /// its own.
+    pub(crate) active_session: Option<SessionId>,
+
+    /// Human-readable device label (`"CPU"`/`"CUDA"`/`"Metal"`), reported by
+    /// [`LLMRuntimeModel::status`]. Computed once in [`LLMRuntime::detect_model`](crate::runtime::LLMRuntime::detect_model).
+    pub(crate) device_name: String,
+
+    /// Configured [`ModelConfig::name`], reported by [`LLMRuntimeModel::status`].
+    pub(crate) model_name: String,
+
+    /// Timing for the most recently completed generation.
+    pub(crate) last_metrics: Option<crate::GenerationMetrics>,
+
+    /// Timing accumulated so far for a generation still in progress.
+    pub(crate) in_flight_metrics: Option<crate::GenerationMetrics>,
 }
 
 impl LLMRuntimeModel for LLama3Model {
-    fn execute(&mut self, message: LlmMessage) -> anyhow::Result<LlmMessage, Error> {
-        if let LlmMessage::Prompt {
-            system: _,
-            message,
-            num_samples,
-        } = message
-        {
-            tracing::debug!("Processing Message: {:?}", message);
-
-            // get defaults
-            let tokenizer = self.tokenizer.as_ref().unwrap();
-            let model = self.weights.as_mut().unwrap();
-            let logits_processor = self.logits_processor.as_mut().unwrap();
-            let device = self.device.as_ref().unwrap();
-
-            // encode message
-            let tokens = tokenizer
-                .encode(message, true)
-                .map_err(|e| Error::MessageEncodingError(e.to_string()))?;
-
-            let tokens = tokens.get_ids();
-
-            // set next token
-            let mut next_token = {
-                let input = Tensor::new(tokens, &device)
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?
-                    .unsqueeze(0)
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?;
-                let logits = model
-                    .forward(&input, 0, self.cache.as_mut().unwrap())
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?;
-                let logits = logits
-                    .squeeze(0)
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?;
-                logits_processor
-                    .sample(&logits)
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?
-            };
+    fn execute(
+        &mut self,
+        message: LlmMessage,
+        chunk_tx: &Sender<LlmMessage>,
+    ) -> anyhow::Result<LlmMessage, Error> {
+        // Build the text for this turn only. A `Prompt` starts a fresh KV
+        // cache at position 0; a `ToolResult` encodes just the tool's output
+        // and resumes from `self.next_position`, so the model keeps whatever
+        // it had already attended to instead of reprocessing the transcript.
+        let prompt_text = match message {
+            LlmMessage::Prompt {
+                system,
+                message,
+                num_samples,
+                session_id,
+            } => {
+                tracing::debug!("Processing Message: {:?}", message);
 
-            let mut all_tokens = vec![];
-            all_tokens.push(next_token);
+                if session_id.is_none() {
+                    self.next_position = 0;
+                }
+                self.active_session = session_id;
+                self.tool_iterations = 0;
+                self.seen_tool_calls.clear();
+                self.num_samples = num_samples;
+
+                let mut text = String::new();
+                if let Some(system) = system {
+                    text.push_str(&format!(
+                        "<|start_header_id|>system<|end_header_id|>\n\n{system}<|eot_id|>"
+                    ));
+                }
+                text.push_str(&format!(
+                    "<|start_header_id|>user<|end_header_id|>\n\n{message}<|eot_id|><|start_header_id|>assistant<|end_header_id|>\n\n"
+                ));
+                text
+            }
+            LlmMessage::ToolResult {
+                call_id: _,
+                name,
+                content,
+            } => {
+                if self.tool_iterations == 0 {
+                    return Err(Error::ExecutionError(
+                        "Received a ToolResult without an active tool-calling round".to_string(),
+                    ));
+                }
 
-            // TODO: set end of stream token
-            let eos_token = *tokenizer.get_vocab(true).get("<|end_of_text|>").unwrap();
-
-            // Start sampling
-            for index in 0..num_samples {
-                let input = Tensor::new(&[next_token], &device)
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?
-                    .unsqueeze(0)
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?;
-                let logits = model
-                    .forward(&input, tokens.len() + index, self.cache.as_mut().unwrap())
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?;
-                let logits = logits
-                    .squeeze(0)
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?;
-
-                let logits = candle_transformers::utils::apply_repeat_penalty(
-                    &logits,
-                    self.penalty,
-                    &all_tokens[0..],
+                tracing::debug!("Processing ToolResult for `{}`", name);
+
+                format!(
+                    "<|start_header_id|>ipython<|end_header_id|>\n\n{name}: {content}<|eot_id|><|start_header_id|>assistant<|end_header_id|>\n\n"
                 )
-                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+            }
+            _ => return Err(Error::ExecutionError("".to_string())),
+        };
 
-                next_token = logits_processor
-                    .sample(&logits)
-                    .map_err(|e| Error::ExecutionError(e.to_string()))?;
-                all_tokens.push(next_token);
+        // get defaults
+        let tokenizer = self.tokenizer.as_ref().unwrap();
+        let model = self.weights.as_mut().unwrap();
+        let logits_processor = self.logits_processor.as_mut().unwrap();
+        let device = self.device.as_ref().unwrap();
+
+        // encode this turn
+        let tokens = tokenizer
+            .encode(prompt_text, true)
+            .map_err(|e| Error::MessageEncodingError(e.to_string()))?;
+
+        let tokens = tokens.get_ids();
+
+        // Resolve which KV cache this turn continues, and at what position.
+        // A session-less prompt uses the model's single scratch cache; a
+        // session reuses (or lazily creates) its own, so only the new suffix
+        // ever needs to be tokenized and forwarded.
+        let base_position = match &self.active_session {
+            Some(id) => self
+                .sessions
+                .get(id)
+                .map(|state| state.committed_tokens)
+                .unwrap_or(0),
+            None => self.next_position,
+        };
 
-                if next_token == eos_token {
-                    break;
+        let cache: &mut model::Cache = match self.active_session.clone() {
+            Some(id) => {
+                if !self.sessions.contains_key(&id) {
+                    let fresh = Self::build_cache(self.model_cfg.as_ref(), self.device.as_ref())?;
+                    self.sessions.insert(
+                        id.clone(),
+                        SessionState {
+                            cache: fresh,
+                            committed_tokens: 0,
+                        },
+                    );
                 }
+                &mut self.sessions.get_mut(&id).unwrap().cache
             }
+            None => self.cache.as_mut().unwrap(),
+        };
 
-            let message = match tokenizer.decode(&all_tokens, true) {
-                Ok(str) => str,
-                Err(e) => return Err(Error::ExecutionError(e.to_string())),
-            };
+        let prompt_eval_start = std::time::Instant::now();
 
-            return Ok(LlmMessage::Response {
-                error: None,
-                message,
-            });
+        // set next token
+        let mut next_token = {
+            let input = Tensor::new(tokens, &device)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?
+                .unsqueeze(0)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+            let logits = model
+                .forward(&input, base_position, cache)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+            let logits = logits
+                .squeeze(0)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+            logits_processor
+                .sample(&logits)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?
+        };
+
+        let prompt_eval_seconds = prompt_eval_start.elapsed().as_secs_f64();
+        self.in_flight_metrics = Some(crate::GenerationMetrics {
+            prompt_tokens: tokens.len(),
+            generated_tokens: 0,
+            prompt_eval_seconds,
+            decode_seconds: 0.0,
+        });
+
+        let mut all_tokens = vec![];
+        all_tokens.push(next_token);
+
+        let mut token_stream = TokenOutputStream::new();
+        let mut output = String::new();
+        let mut flushed = 0;
+        let mut confirmed_plain_text = false;
+
+        if let Some(delta) = token_stream.next_token(tokenizer, next_token)? {
+            Self::buffer_or_emit(
+                &mut output,
+                &mut flushed,
+                &mut confirmed_plain_text,
+                chunk_tx,
+                delta,
+                self.streaming,
+            );
+        }
+
+        // TODO: set end of stream token
+        let eos_token = *tokenizer.get_vocab(true).get("<|end_of_text|>").unwrap();
+
+        // Start sampling
+        let decode_start = std::time::Instant::now();
+        let mut generated = 0;
+        for index in 0..self.num_samples {
+            generated = index + 1;
+
+            let input = Tensor::new(&[next_token], &device)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?
+                .unsqueeze(0)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+            let logits = model
+                .forward(&input, base_position + tokens.len() + index, cache)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+            let logits = logits
+                .squeeze(0)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+
+            let penalty_start = all_tokens.len().saturating_sub(self.repeat_last_n);
+            let logits = candle_transformers::utils::apply_repeat_penalty(
+                &logits,
+                self.penalty,
+                &all_tokens[penalty_start..],
+            )
+            .map_err(|e| Error::ExecutionError(e.to_string()))?;
+
+            next_token = logits_processor
+                .sample(&logits)
+                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+            all_tokens.push(next_token);
+
+            if let Some(delta) = token_stream.next_token(tokenizer, next_token)? {
+                Self::buffer_or_emit(
+                    &mut output,
+                    &mut flushed,
+                    &mut confirmed_plain_text,
+                    chunk_tx,
+                    delta,
+                    self.streaming,
+                );
+            }
+
+            if let Some(metrics) = self.in_flight_metrics.as_mut() {
+                metrics.generated_tokens = generated;
+                metrics.decode_seconds = decode_start.elapsed().as_secs_f64();
+            }
+
+            if next_token == eos_token {
+                break;
+            }
+        }
+
+        if let Some(delta) = token_stream.finalize(tokenizer)? {
+            Self::buffer_or_emit(
+                &mut output,
+                &mut flushed,
+                &mut confirmed_plain_text,
+                chunk_tx,
+                delta,
+                self.streaming,
+            );
+        }
+
+        self.last_metrics = Some(crate::GenerationMetrics {
+            prompt_tokens: tokens.len(),
+            generated_tokens: generated,
+            prompt_eval_seconds,
+            decode_seconds: decode_start.elapsed().as_secs_f64(),
+        });
+        self.in_flight_metrics = None;
+
+        let committed = base_position + tokens.len() + generated;
+        match self.active_session.clone() {
+            Some(id) => {
+                if let Some(state) = self.sessions.get_mut(&id) {
+                    state.committed_tokens = committed;
+                }
+            }
+            None => self.next_position = committed,
         }
 
-        Err(Error::ExecutionError("".to_string()))
+        if self.tool_iterations < self.max_tool_iterations {
+            if let Some(calls) = LlamaToolCallParser.parse(&output) {
+                let keys: Vec<String> = calls
+                    .iter()
+                    .map(|call| format!("{}:{}", call.name, call.arguments))
+                    .collect();
+
+                if keys.iter().all(|key| self.seen_tool_calls.contains(key)) {
+                    tracing::warn!(
+                        "Model re-issued an already-seen tool call; ending the tool-calling round"
+                    );
+                } else {
+                    self.seen_tool_calls.extend(keys);
+                    self.tool_iterations += 1;
+                    return Ok(LlmMessage::ToolCall { calls });
+                }
+            }
+        }
+
+        Ok(LlmMessage::Response {
+            error: None,
+            message: output,
+            reasoning: None,
+        })
     }
 
     fn init(&mut self, config: &LLMRuntimeConfig) -> anyhow::Result<(), Error> {
@@ -129,16 +364,19 @@ impl LLMRuntimeModel for LLama3Model {
             model_dir,
             model_config: _,
             verbose: _,
+            ..
         } = config;
 
         let ModelConfig {
             seed,
             sampling_config,
             penalty,
+            repeat_last_n,
             ..
         } = config.model_config.clone();
 
         self.penalty = penalty;
+        self.repeat_last_n = repeat_last_n;
 
         // Initialize the tokenizer
         self.tokenizer = Some(
@@ -153,6 +391,7 @@ impl LLMRuntimeModel for LLama3Model {
         let mut llama_config_file = File::open(model_config_file.as_ref().unwrap())?;
         let cfg: LlamaConfig = serde_json::from_reader(&mut llama_config_file)?;
         let cfg = cfg.into_config(false);
+        self.model_cfg = Some(cfg.clone());
 
         // Load weights
         self.weights = {
@@ -168,9 +407,7 @@ impl LLMRuntimeModel for LLama3Model {
                 .map_err(|e| Error::ExecutionError(e.to_string()))?
             };
 
-            Llama::load(vb, &cfg)
-                .map_err(|e| Error::ExecutionError(e.to_string()))
-                .ok()
+            Some(Llama::load(vb, &cfg).map_err(|e| Error::ExecutionError(e.to_string()))?)
         };
 
         self.cache = {
@@ -227,5 +464,108 @@ impl LLMRuntimeModel for LLama3Model {
         Ok(())
     }
 
-    fn apply_chat_template(&mut self, template: String) {}
+    fn apply_chat_template(&mut self, _template: String) {}
+
+    /// Embeddings are not supported for [`LLama3Model`].
+    ///
+    /// `candle_transformers::models::llama::Llama::forward` only exposes the
+    /// lm_head projection for the final position, not the per-layer hidden
+    /// states mean-pooling needs — its output is `vocab_size`-wide logits,
+    /// not a hidden-size vector, and cosine similarity over it would be
+    /// meaningless. Rather than silently return that as an "embedding", this
+    /// falls back to the trait's default unsupported error until a
+    /// hidden-states-returning entry point is wired up.
+    fn embed(&mut self, _inputs: Vec<String>) -> Result<Vec<Vec<f32>>, Error> {
+        Err(Error::ExecutionError(
+            "LLama3Model does not support embeddings: Llama::forward only returns lm_head logits, not hidden states".to_string(),
+        ))
+    }
+
+    /// Starts a new persistent chat session with its own KV cache.
+    fn new_session(&mut self, session_id: SessionId) -> Result<(), Error> {
+        let cache = Self::build_cache(self.model_cfg.as_ref(), self.device.as_ref())?;
+        self.sessions.insert(
+            session_id,
+            SessionState {
+                cache,
+                committed_tokens: 0,
+            },
+        );
+        Ok(())
+    }
+
+    /// Frees the KV cache associated with `session_id`, if any.
+    fn drop_session(&mut self, session_id: SessionId) -> Result<(), Error> {
+        self.sessions.remove(&session_id);
+        Ok(())
+    }
+
+    fn status(&self) -> LlmMessage {
+        LlmMessage::StatusReport {
+            device: self.device_name.clone(),
+            model_name: self.model_name.clone(),
+            last: self.last_metrics.clone(),
+            in_flight: self.in_flight_metrics.clone(),
+        }
+    }
+}
+
+impl LLama3Model {
+    /// Streams a decoded delta back to the caller over the response channel.
+    ///
+    /// Emission is best-effort: if the receiver has gone away, the generation
+    /// loop keeps running and the full text is still returned at the end.
+    fn emit_chunk(chunk_tx: &Sender<LlmMessage>, delta: String) {
+        if let Err(error) = chunk_tx.send(LlmMessage::Chunk(delta)) {
+            tracing::error!("Error sending streamed chunk: {}", error);
+        }
+    }
+
+    /// Appends `delta` to `output`, withholding it from the stream while
+    /// [`LlamaToolCallParser::parse_streaming`] says a tool call may still be
+    /// forming. Once confirmed plain text, the withheld tail is flushed and
+    /// every later delta is emitted immediately. No chunk is ever sent when
+    /// `streaming` is `false`; `output` still accumulates so the full text is
+    /// returned once generation finishes.
+    fn buffer_or_emit(
+        output: &mut String,
+        flushed: &mut usize,
+        confirmed_plain_text: &mut bool,
+        chunk_tx: &Sender<LlmMessage>,
+        delta: String,
+        streaming: bool,
+    ) {
+        output.push_str(&delta);
+
+        if !streaming {
+            return;
+        }
+
+        if *confirmed_plain_text {
+            Self::emit_chunk(chunk_tx, delta);
+            return;
+        }
+
+        if let ToolCallParseState::PlainText = LlamaToolCallParser.parse_streaming(output) {
+            *confirmed_plain_text = true;
+            let pending = output[*flushed..].to_string();
+            *flushed = output.len();
+            if !pending.is_empty() {
+                Self::emit_chunk(chunk_tx, pending);
+            }
+        }
+    }
+
+    /// Builds a fresh KV cache for a new session, using the config captured
+    /// from [`LLMRuntimeModel::init`].
+    fn build_cache(
+        cfg: Option<&model::Config>,
+        device: Option<&Device>,
+    ) -> Result<model::Cache, Error> {
+        let cfg = cfg.ok_or(Error::MissingConfigLLM("Model config is missing".to_owned()))?;
+        let device = device.ok_or(Error::MissingDevice)?;
+
+        model::Cache::new(false, candle_core::DType::BF16, cfg, device)
+            .map_err(|e| Error::ExecutionError(e.to_string()))
+    }
 }
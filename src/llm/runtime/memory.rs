@@ -0,0 +1,161 @@
+//! Context-retrieval backends consulted before prompt execution.
+//!
+//! See [`crate::runtime::MemoryBackend`] for the trait both implementations
+//! here satisfy.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::llm::rag::DocumentStore;
+use crate::runtime::{LLMRuntimeModel, MemoryBackend};
+
+/// Crude token-count estimate: ~4 characters per token.
+fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Retrieves passages from `.txt` files in a directory, ranked by how many
+/// words they share with the query.
+///
+/// Needs no embeddings, so it works even for models that don't implement
+/// [`LLMRuntimeModel::embed`].
+pub struct FileMemoryBackend {
+    documents: Vec<(String, String)>,
+}
+
+impl FileMemoryBackend {
+    pub fn new(directory: &Path) -> Result<Self, Error> {
+        let mut documents = Vec::new();
+
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("txt") {
+                continue;
+            }
+
+            match fs::read_to_string(&path) {
+                Ok(text) => documents.push((path.display().to_string(), text)),
+                Err(error) => tracing::warn!("Could not read {}: {}", path.display(), error),
+            }
+        }
+
+        Ok(Self { documents })
+    }
+
+    /// An empty store, used as a fallback if [`Self::new`] fails to read `directory`.
+    pub fn empty() -> Self {
+        Self {
+            documents: Vec::new(),
+        }
+    }
+}
+
+impl MemoryBackend for FileMemoryBackend {
+    fn add_document(
+        &mut self,
+        id: String,
+        text: String,
+        _model: &mut dyn LLMRuntimeModel,
+    ) -> Result<(), Error> {
+        self.documents.retain(|(existing_id, _)| *existing_id != id);
+        self.documents.push((id, text));
+        Ok(())
+    }
+
+    fn get_context(
+        &mut self,
+        query: &str,
+        max_tokens: usize,
+        _model: &mut dyn LLMRuntimeModel,
+    ) -> Result<Vec<String>, Error> {
+        let query_words: HashSet<&str> = query.split_whitespace().collect();
+
+        let mut scored: Vec<(usize, &str)> = self
+            .documents
+            .iter()
+            .map(|(_, text)| {
+                let score = text
+                    .split_whitespace()
+                    .filter(|word| query_words.contains(word))
+                    .count();
+                (score, text.as_str())
+            })
+            .filter(|(score, _)| *score > 0)
+            .collect();
+
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+
+        let mut budget = max_tokens;
+        let mut context = Vec::new();
+        for (_, text) in scored {
+            let cost = estimate_tokens(text);
+            if cost > budget {
+                break;
+            }
+            budget -= cost;
+            context.push(text.to_string());
+        }
+
+        Ok(context)
+    }
+}
+
+/// Retrieves passages from an in-memory cosine-similarity vector store,
+/// embedding both documents and queries via the active [`LLMRuntimeModel`].
+pub struct VectorMemoryBackend {
+    store: DocumentStore,
+    top_k: usize,
+}
+
+impl VectorMemoryBackend {
+    pub fn new(top_k: usize) -> Self {
+        Self {
+            store: DocumentStore::new(),
+            top_k,
+        }
+    }
+}
+
+impl MemoryBackend for VectorMemoryBackend {
+    fn add_document(
+        &mut self,
+        id: String,
+        text: String,
+        model: &mut dyn LLMRuntimeModel,
+    ) -> Result<(), Error> {
+        let mut embeddings = model.embed(vec![text.clone()])?;
+        let embedding = embeddings
+            .pop()
+            .ok_or_else(|| Error::ExecutionError("Embedding returned no vectors".to_string()))?;
+
+        self.store.insert(id, text, embedding);
+        Ok(())
+    }
+
+    fn get_context(
+        &mut self,
+        query: &str,
+        max_tokens: usize,
+        model: &mut dyn LLMRuntimeModel,
+    ) -> Result<Vec<String>, Error> {
+        let mut embeddings = model.embed(vec![query.to_string()])?;
+        let Some(query_embedding) = embeddings.pop() else {
+            return Ok(Vec::new());
+        };
+
+        let mut budget = max_tokens;
+        let mut context = Vec::new();
+        for document in self.store.search(&query_embedding, self.top_k) {
+            let cost = estimate_tokens(&document.text);
+            if cost > budget {
+                break;
+            }
+            budget -= cost;
+            context.push(document.text.clone());
+        }
+
+        Ok(context)
+    }
+}
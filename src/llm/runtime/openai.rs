@@ -0,0 +1,96 @@
+use std::sync::mpsc::Sender;
+
+use crate::error::Error;
+use crate::llm::llmconfig::LLMRuntimeConfig;
+use crate::runtime::{LLMRuntimeModel, LlmMessage, TransformBackend};
+
+/// Forwards generation requests to a remote OpenAI-compatible `/chat/completions` endpoint.
+///
+/// Selected via [`crate::llmconfig::ValidTransformerBackend::OpenAiCompatible`]. Implements
+/// the same [`LLMRuntimeModel`] trait as [`crate::runtime::llama3::LLama3Model`] and
+/// [`crate::runtime::qwen3::Qwen3Model`], so [`crate::runtime::LLMRuntime`] can dispatch to
+/// it without the frontend changing any call sites.
+pub struct OpenAiBackend {
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
+    client: reqwest::blocking::Client,
+}
+
+impl OpenAiBackend {
+    pub fn new(endpoint: String, api_key: Option<String>, model: String) -> Self {
+        Self {
+            endpoint,
+            api_key,
+            model,
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+}
+
+impl TransformBackend for OpenAiBackend {
+    fn do_completion(&mut self, prompt: &str, num_samples: usize) -> Result<String, Error> {
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", self.endpoint))
+            .json(&serde_json::json!({
+                "model": self.model,
+                "max_tokens": num_samples,
+                "messages": [{"role": "user", "content": prompt}],
+            }));
+
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: serde_json::Value = request
+            .send()
+            .map_err(|e| Error::ExecutionError(e.to_string()))?
+            .json()
+            .map_err(|e| Error::ExecutionError(e.to_string()))?;
+
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| Error::ExecutionError("Malformed completion response".to_string()))
+    }
+}
+
+impl LLMRuntimeModel for OpenAiBackend {
+    fn execute(
+        &mut self,
+        message: LlmMessage,
+        chunk_tx: &Sender<LlmMessage>,
+    ) -> Result<LlmMessage, Error> {
+        if let LlmMessage::Prompt {
+            system,
+            message,
+            num_samples,
+            session_id: _,
+        } = message
+        {
+            let prompt = match system {
+                Some(system) => format!("{system}\n\n{message}"),
+                None => message,
+            };
+
+            let message = self.do_generate_stream(&prompt, num_samples, chunk_tx)?;
+
+            return Ok(LlmMessage::Response {
+                error: None,
+                message,
+                reasoning: None,
+            });
+        }
+
+        Err(Error::ExecutionError("".to_string()))
+    }
+
+    fn init(&mut self, _config: &LLMRuntimeConfig) -> Result<(), Error> {
+        // The HTTP client is constructed eagerly in `Self::new`; there is no
+        // local model to load.
+        Ok(())
+    }
+
+    fn apply_chat_template(&mut self, _template: String) {}
+}
@@ -1,17 +1,45 @@
 use std::fs::File;
+use std::sync::mpsc::Sender;
 
 use crate::error::Error;
-use crate::llmconfig::{LLMRuntimeConfig, ModelConfig};
+use crate::llmconfig::{LLMRuntimeConfig, ModelConfig, ModelFileType};
+use crate::loaders::safetensor::IndexFile;
+use crate::runtime::token_stream::TokenOutputStream;
+use crate::runtime::tool_call::{Qwen3ToolCallParser, ToolCallParseState, ToolCallParser};
 use crate::runtime::{LLMRuntimeModel, LlmMessage};
 use candle_core::Device;
 use candle_core::{quantized::gguf_file, Tensor};
+use candle_nn::VarBuilder;
 use candle_transformers::{
     generation::{LogitsProcessor, Sampling},
-    models::quantized_qwen3::ModelWeights as Qwen3,
+    models::qwen3::{Config as Qwen3Config, ModelForCausalLM as Qwen3SafetensorsModel},
+    models::quantized_qwen3::ModelWeights as QuantizedQwen3,
 };
 use rand::Rng;
 use tokenizers::Tokenizer;
 
+/// The loaded Qwen3 weights, in whichever format [`ModelConfig::file_type`] selected.
+///
+/// [`Self::forward`] returns last-token logits shaped `[batch, vocab]` for
+/// both variants, so [`Qwen3Model::execute`] doesn't need to know which one
+/// is active.
+pub(crate) enum Qwen3Weights {
+    /// A `*.gguf` file quantized ahead of time, loaded whole into memory.
+    Quantized(QuantizedQwen3),
+
+    /// Full-precision weights loaded from sharded `*.safetensors` files.
+    Safetensors(Qwen3SafetensorsModel),
+}
+
+impl Qwen3Weights {
+    fn forward(&mut self, input: &Tensor, index: usize) -> candle_core::Result<Tensor> {
+        match self {
+            Qwen3Weights::Quantized(model) => model.forward(input, index),
+            Qwen3Weights::Safetensors(model) => model.forward(input, index)?.squeeze(1),
+        }
+    }
+}
+
 pub struct Qwen3Model {
     pub(crate) streaming: bool,
     pub(crate) device: Option<Device>,
@@ -20,20 +48,41 @@ pub struct Qwen3Model {
     pub(crate) top_p: f64,
     pub(crate) temperature: f64,
     pub(crate) thinking: bool,
-    pub(crate) weights: Option<Qwen3>,
+    pub(crate) weights: Option<Qwen3Weights>,
     pub(crate) logits_processor: Option<LogitsProcessor>,
+
+    /// Repetition penalty
+    pub(crate) penalty: f32,
+
+    /// Number of most recently generated tokens scanned when applying
+    /// [`Self::penalty`].
+    pub(crate) repeat_last_n: usize,
+
+    /// Human-readable device label (`"CPU"`/`"CUDA"`/`"Metal"`), reported by
+    /// [`LLMRuntimeModel::status`]. Computed once in [`LLMRuntime::detect_model`](crate::runtime::LLMRuntime::detect_model).
+    pub(crate) device_name: String,
+
+    /// Configured [`ModelConfig::name`], reported by [`LLMRuntimeModel::status`].
+    pub(crate) model_name: String,
+
+    /// Timing for the most recently completed generation.
+    pub(crate) last_metrics: Option<crate::GenerationMetrics>,
+
+    /// Timing accumulated so far for a generation still in progress.
+    pub(crate) in_flight_metrics: Option<crate::GenerationMetrics>,
 }
 
 impl LLMRuntimeModel for Qwen3Model {
-    /// TODO:
-    /// - apply penalty for repetitions
-    /// - enable thinking mode
-    /// - enable setting a system message
-    fn execute(&mut self, message: LlmMessage) -> Result<LlmMessage, Error> {
+    fn execute(
+        &mut self,
+        message: LlmMessage,
+        chunk_tx: &Sender<LlmMessage>,
+    ) -> Result<LlmMessage, Error> {
         if let LlmMessage::Prompt {
-            system: _,
+            system,
             message,
             num_samples,
+            session_id: _,
         } = message
         {
             tracing::debug!("Processing Message: {:?}", message);
@@ -44,13 +93,17 @@ impl LLMRuntimeModel for Qwen3Model {
             let logits_processor = self.logits_processor.as_mut().unwrap();
             let device = self.device.as_ref().unwrap();
 
+            let prompt = Self::build_chatml_prompt(system.as_deref(), &message, self.thinking);
+
             // encode message
             let tokens = tokenizer
-                .encode(message, true)
+                .encode(prompt, true)
                 .map_err(|e| Error::MessageEncodingError(e.to_string()))?;
 
             let tokens = tokens.get_ids();
 
+            let prompt_eval_start = std::time::Instant::now();
+
             // set next token
             let mut next_token = {
                 let input = Tensor::new(tokens, &device)
@@ -68,13 +121,42 @@ impl LLMRuntimeModel for Qwen3Model {
                     .map_err(|e| Error::ExecutionError(e.to_string()))?
             };
 
-            let mut all_tokens = vec![];
-            all_tokens.push(next_token);
+            let prompt_eval_seconds = prompt_eval_start.elapsed().as_secs_f64();
+            self.in_flight_metrics = Some(crate::GenerationMetrics {
+                prompt_tokens: tokens.len(),
+                generated_tokens: 0,
+                prompt_eval_seconds,
+                decode_seconds: 0.0,
+            });
+
+            // Seeded with the prompt-priming token so the repeat-penalty window
+            // (see `self.penalty`/`self.repeat_last_n` below) slides correctly
+            // from the very first generated token, not just the ones sampled
+            // inside the loop.
+            let mut all_tokens = vec![next_token];
+
+            let mut token_stream = TokenOutputStream::new();
+            let mut output = String::new();
+            let mut flushed = 0;
+            let mut confirmed_plain_text = false;
+
+            if let Some(delta) = token_stream.next_token(tokenizer, next_token)? {
+                Self::buffer_or_emit(
+                    &mut output,
+                    &mut flushed,
+                    &mut confirmed_plain_text,
+                    chunk_tx,
+                    delta,
+                    self.streaming,
+                );
+            }
 
             // set end of stream token
             let eos_token = *tokenizer.get_vocab(true).get("<|im_end|>").unwrap();
 
             // Start sampling
+            let decode_start = std::time::Instant::now();
+            let mut generated = 0;
             for index in 0..num_samples {
                 let input = Tensor::new(&[next_token], &device)
                     .map_err(|e| Error::ExecutionError(e.to_string()))?
@@ -87,24 +169,66 @@ impl LLMRuntimeModel for Qwen3Model {
                     .squeeze(0)
                     .map_err(|e| Error::ExecutionError(e.to_string()))?;
 
+                let penalty_start = all_tokens.len().saturating_sub(self.repeat_last_n);
+                let logits = candle_transformers::utils::apply_repeat_penalty(
+                    &logits,
+                    self.penalty,
+                    &all_tokens[penalty_start..],
+                )
+                .map_err(|e| Error::ExecutionError(e.to_string()))?;
+
                 next_token = logits_processor
                     .sample(&logits)
                     .map_err(|e| Error::ExecutionError(e.to_string()))?;
                 all_tokens.push(next_token);
 
+                if let Some(delta) = token_stream.next_token(tokenizer, next_token)? {
+                    Self::buffer_or_emit(
+                        &mut output,
+                        &mut flushed,
+                        &mut confirmed_plain_text,
+                        chunk_tx,
+                        delta,
+                        self.streaming,
+                    );
+                }
+
+                generated = index + 1;
+                if let Some(metrics) = self.in_flight_metrics.as_mut() {
+                    metrics.generated_tokens = generated;
+                    metrics.decode_seconds = decode_start.elapsed().as_secs_f64();
+                }
+
                 if next_token == eos_token {
                     break;
                 }
             }
 
-            let message = match tokenizer.decode(&all_tokens, true) {
-                Ok(str) => str,
-                Err(e) => return Err(Error::ExecutionError(e.to_string())),
-            };
+            if let Some(delta) = token_stream.finalize(tokenizer)? {
+                Self::buffer_or_emit(
+                    &mut output,
+                    &mut flushed,
+                    &mut confirmed_plain_text,
+                    chunk_tx,
+                    delta,
+                    self.streaming,
+                );
+            }
+
+            self.last_metrics = Some(crate::GenerationMetrics {
+                prompt_tokens: tokens.len(),
+                generated_tokens: generated,
+                prompt_eval_seconds,
+                decode_seconds: decode_start.elapsed().as_secs_f64(),
+            });
+            self.in_flight_metrics = None;
+
+            let (reasoning, message) = Self::split_reasoning(output);
 
             return Ok(LlmMessage::Response {
                 error: None,
                 message,
+                reasoning,
             });
         }
 
@@ -115,9 +239,17 @@ impl LLMRuntimeModel for Qwen3Model {
         let ModelConfig {
             seed,
             sampling_config,
+            file_type,
+            penalty,
+            repeat_last_n,
+            thinking,
             ..
         } = config.model_config.clone();
 
+        self.penalty = penalty;
+        self.repeat_last_n = repeat_last_n;
+        self.thinking = thinking;
+
         // Initialize the tokenizer
         self.tokenizer = Some(
             Tokenizer::from_file(&config.tokenizer_config_file.as_ref().ok_or(
@@ -126,23 +258,54 @@ impl LLMRuntimeModel for Qwen3Model {
             .map_err(|e| Error::LoadingFile(e.to_string()))?,
         );
 
-        // Load weights
-        self.weights = {
-            let mut model_file = File::open(config.model_file.as_ref().ok_or(
-                Error::MissingConfigLLM("Model config file is missing".to_owned()),
-            )?)?;
-            let model = gguf_file::Content::read(&mut model_file)
-                .map_err(|e| Error::LoadingFile(e.to_string()))?;
-
-            Some(
-                Qwen3::from_gguf(
-                    model,
-                    &mut model_file,
-                    self.device.as_ref().ok_or(Error::MissingDevice)?,
+        let device = self.device.as_ref().ok_or(Error::MissingDevice)?;
+
+        // Load weights, dispatching on the configured file type so a quantized
+        // `*.gguf` model can run on CPU-only machines with a fraction of the
+        // memory a full-precision `*.safetensors` model needs.
+        self.weights = Some(match file_type {
+            ModelFileType::GGUF => {
+                let mut model_file = File::open(config.model_file.as_ref().ok_or(
+                    Error::MissingConfigLLM("Model file is missing".to_owned()),
+                )?)?;
+                let model = gguf_file::Content::read(&mut model_file)
+                    .map_err(|e| Error::LoadingFile(e.to_string()))?;
+
+                Qwen3Weights::Quantized(
+                    QuantizedQwen3::from_gguf(model, &mut model_file, device)
+                        .map_err(|e| Error::LoadingFile(e.to_string()))?,
                 )
-                .map_err(|e| Error::LoadingFile(e.to_string()))?,
-            )
-        };
+            }
+            ModelFileType::Safetensors => {
+                let mut index_file = IndexFile::from_path(config.model_index_file.as_ref().ok_or(
+                    Error::MissingConfigLLM("Model index file is missing".to_owned()),
+                )?)?;
+                let paths = index_file.files(config.model_dir.as_ref().ok_or(
+                    Error::MissingConfigLLM("Model directory is missing".to_owned()),
+                )?);
+
+                let vb = unsafe {
+                    VarBuilder::from_mmaped_safetensors(&paths, candle_core::DType::BF16, device)
+                        .map_err(|e| Error::ExecutionError(e.to_string()))?
+                };
+
+                let mut model_config_file = File::open(config.model_config_file.as_ref().ok_or(
+                    Error::MissingConfigLLM("Model config file is missing".to_owned()),
+                )?)?;
+                let cfg: Qwen3Config = serde_json::from_reader(&mut model_config_file)
+                    .map_err(|e| Error::MessageEncodingError(e.to_string()))?;
+
+                Qwen3Weights::Safetensors(
+                    Qwen3SafetensorsModel::new(&cfg, vb)
+                        .map_err(|e| Error::ExecutionError(e.to_string()))?,
+                )
+            }
+            ModelFileType::Pickle => {
+                return Err(Error::ExecutionError(
+                    "Qwen3 does not support loading *.pth weights".to_string(),
+                ))
+            }
+        });
 
         // Initialize Logits Processor
         self.logits_processor = {
@@ -185,4 +348,109 @@ impl LLMRuntimeModel for Qwen3Model {
 
         Ok(())
     }
+
+    fn status(&self) -> LlmMessage {
+        LlmMessage::StatusReport {
+            device: self.device_name.clone(),
+            model_name: self.model_name.clone(),
+            last: self.last_metrics.clone(),
+            in_flight: self.in_flight_metrics.clone(),
+        }
+    }
+}
+
+impl Qwen3Model {
+    /// Builds the Qwen3 ChatML prompt: a system turn (defaulted when `system`
+    /// is `None`), the user turn, and an opened assistant turn for the model
+    /// to continue.
+    ///
+    /// When `thinking` is `false`, an empty `<think>\n\n</think>\n\n` block is
+    /// appended after the assistant tag, which is Qwen3's documented
+    /// convention for disabling reasoning for this turn.
+    fn build_chatml_prompt(system: Option<&str>, message: &str, thinking: bool) -> String {
+        let system = system.unwrap_or("You are a helpful assistant.");
+
+        let mut prompt = format!(
+            "<|im_start|>system\n{system}<|im_end|>\n<|im_start|>user\n{message}<|im_end|>\n<|im_start|>assistant\n"
+        );
+
+        if !thinking {
+            prompt.push_str("<think>\n\n</think>\n\n");
+        }
+
+        prompt
+    }
+
+    /// Splits generated text on a `<think>...</think>` span, returning the
+    /// reasoning separately from the final answer. Returns `(None, output)`
+    /// unchanged if no such span is present.
+    fn split_reasoning(output: String) -> (Option<String>, String) {
+        let Some(start) = output.find("<think>") else {
+            return (None, output);
+        };
+        let Some(end) = output.find("</think>") else {
+            return (None, output);
+        };
+
+        let reasoning_start = start + "<think>".len();
+        if reasoning_start > end {
+            return (None, output);
+        }
+
+        let reasoning = output[reasoning_start..end].trim().to_string();
+        let answer = format!(
+            "{}{}",
+            &output[..start],
+            &output[end + "</think>".len()..]
+        )
+        .trim()
+        .to_string();
+
+        (Some(reasoning), answer)
+    }
+
+    /// Streams a decoded delta back to the caller over the response channel.
+    ///
+    /// Emission is best-effort: if the receiver has gone away, the generation
+    /// loop keeps running and the full text is still returned at the end.
+    fn emit_chunk(chunk_tx: &Sender<LlmMessage>, delta: String) {
+        if let Err(error) = chunk_tx.send(LlmMessage::Chunk(delta)) {
+            tracing::error!("Error sending streamed chunk: {}", error);
+        }
+    }
+
+    /// Appends `delta` to `output`, withholding it from the stream while
+    /// [`Qwen3ToolCallParser::parse_streaming`] says a tool call may still be
+    /// forming. Once confirmed plain text, the withheld tail is flushed and
+    /// every later delta is emitted immediately. No chunk is ever sent when
+    /// `streaming` is `false`; `output` still accumulates so the full text is
+    /// returned once generation finishes.
+    fn buffer_or_emit(
+        output: &mut String,
+        flushed: &mut usize,
+        confirmed_plain_text: &mut bool,
+        chunk_tx: &Sender<LlmMessage>,
+        delta: String,
+        streaming: bool,
+    ) {
+        output.push_str(&delta);
+
+        if !streaming {
+            return;
+        }
+
+        if *confirmed_plain_text {
+            Self::emit_chunk(chunk_tx, delta);
+            return;
+        }
+
+        if let ToolCallParseState::PlainText = Qwen3ToolCallParser.parse_streaming(output) {
+            *confirmed_plain_text = true;
+            let pending = output[*flushed..].to_string();
+            *flushed = output.len();
+            if !pending.is_empty() {
+                Self::emit_chunk(chunk_tx, pending);
+            }
+        }
+    }
 }
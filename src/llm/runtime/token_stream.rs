@@ -0,0 +1,67 @@
+use crate::error::Error;
+use tokenizers::Tokenizer;
+
+/// Decodes sampled tokens incrementally, only releasing text once a full,
+/// valid UTF-8 character has closed.
+///
+/// BPE tokenizers frequently split a single multi-byte UTF-8 character (CJK,
+/// emoji, ...) across several token ids. Decoding tokens one at a time would
+/// emit the replacement character (`U+FFFD`) for the dangling bytes, so this
+/// instead re-decodes the growing tail of the token buffer on every step and
+/// only flushes the newly closed suffix once it's safe to do so.
+///
+/// Adapted from candle's `token_output_stream` example. Shared by every
+/// runtime model (`LLama3Model`, `Qwen3Model`) since the decode algorithm
+/// itself doesn't vary by model family.
+pub(crate) struct TokenOutputStream {
+    tokens: Vec<u32>,
+    prev_index: usize,
+    current_index: usize,
+}
+
+impl TokenOutputStream {
+    pub(crate) fn new() -> Self {
+        Self {
+            tokens: Vec::new(),
+            prev_index: 0,
+            current_index: 0,
+        }
+    }
+
+    fn decode(&self, tokenizer: &Tokenizer, range: std::ops::Range<usize>) -> Result<String, Error> {
+        tokenizer
+            .decode(&self.tokens[range], true)
+            .map_err(|e| Error::MessageEncodingError(e.to_string()))
+    }
+
+    /// Pushes a newly sampled token and returns the delta text, if any new
+    /// complete characters were produced.
+    pub(crate) fn next_token(&mut self, tokenizer: &Tokenizer, token: u32) -> Result<Option<String>, Error> {
+        let prev_text = self.decode(tokenizer, self.prev_index..self.current_index)?;
+
+        self.tokens.push(token);
+
+        let text = self.decode(tokenizer, self.prev_index..self.tokens.len())?;
+
+        if text.len() > prev_text.len() && !text.ends_with('\u{fffd}') {
+            self.prev_index = self.current_index;
+            self.current_index = self.tokens.len();
+            Ok(Some(text[prev_text.len()..].to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Flushes any trailing bytes that were withheld because they hadn't
+    /// closed into a complete character yet.
+    pub(crate) fn finalize(&mut self, tokenizer: &Tokenizer) -> Result<Option<String>, Error> {
+        let prev_text = self.decode(tokenizer, self.prev_index..self.current_index)?;
+        let text = self.decode(tokenizer, self.prev_index..self.tokens.len())?;
+
+        if text.len() > prev_text.len() {
+            Ok(Some(text[prev_text.len()..].to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+}
@@ -1,5 +1,21 @@
 use crate::ToolCall;
 
+/// Result of feeding a growing buffer of decoded text to
+/// [`ToolCallParser::parse_streaming`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolCallParseState {
+    /// A tool call may be forming; withhold emitting this buffer as text and
+    /// wait for more tokens.
+    Incomplete,
+
+    /// The buffer is plain prose — no tool call marker is forming in it, so
+    /// it's safe to stream to the UI as-is.
+    PlainText,
+
+    /// A complete tool call (or batch of them) has closed.
+    Complete(Vec<ToolCall>),
+}
+
 /// Parses tool calls from raw model output text.
 ///
 /// Each model family has its own format for tool calls. This trait
@@ -9,6 +25,21 @@ pub trait ToolCallParser: Send + Sync {
     /// Attempt to parse tool calls from the full decoded model output.
     /// Returns `None` if the output does not contain tool calls.
     fn parse(&self, output: &str) -> Option<Vec<ToolCall>>;
+
+    /// Incremental variant of [`Self::parse`] for streaming decode.
+    ///
+    /// `buffer` is the full text decoded so far for this turn (not just the
+    /// latest delta). Called again with a longer `buffer` as more tokens
+    /// arrive, until it returns [`ToolCallParseState::Complete`] or
+    /// [`ToolCallParseState::PlainText`].
+    fn parse_streaming(&self, buffer: &str) -> ToolCallParseState;
+}
+
+/// Whether `buffer` ends with a non-empty prefix of `marker`, i.e. `marker`
+/// could still appear if more text arrives.
+fn marker_may_be_forming(buffer: &str, marker: &str) -> bool {
+    let max_len = marker.len().min(buffer.len());
+    (1..=max_len).rev().any(|len| buffer.ends_with(&marker[..len]))
 }
 
 /// Llama 3.2 tool call parser.
@@ -39,6 +70,21 @@ impl ToolCallParser for LlamaToolCallParser {
 
         Some(vec![ToolCall::new("call_0".to_string(), name, arguments)])
     }
+
+    fn parse_streaming(&self, buffer: &str) -> ToolCallParseState {
+        const MARKER: &str = r#"{"name""#;
+        let trimmed = buffer.trim_start();
+
+        match trimmed.find(MARKER) {
+            Some(_) if json_object_closed(trimmed) => match self.parse(buffer) {
+                Some(calls) => ToolCallParseState::Complete(calls),
+                None => ToolCallParseState::PlainText,
+            },
+            Some(_) => ToolCallParseState::Incomplete,
+            None if marker_may_be_forming(trimmed, MARKER) => ToolCallParseState::Incomplete,
+            None => ToolCallParseState::PlainText,
+        }
+    }
 }
 
 /// Qwen3 tool call parser.
@@ -88,6 +134,21 @@ impl ToolCallParser for Qwen3ToolCallParser {
             Some(calls)
         }
     }
+
+    fn parse_streaming(&self, buffer: &str) -> ToolCallParseState {
+        const OPEN: &str = "<tool_call>";
+        const CLOSE: &str = "</tool_call>";
+
+        match buffer.find(OPEN) {
+            Some(open_idx) if buffer[open_idx..].contains(CLOSE) => match self.parse(buffer) {
+                Some(calls) => ToolCallParseState::Complete(calls),
+                None => ToolCallParseState::PlainText,
+            },
+            Some(_) => ToolCallParseState::Incomplete,
+            None if marker_may_be_forming(buffer, OPEN) => ToolCallParseState::Incomplete,
+            None => ToolCallParseState::PlainText,
+        }
+    }
 }
 
 /// Finds the first complete JSON object in a string.
@@ -123,6 +184,41 @@ fn find_first_json_object(input: &str) -> Option<serde_json::Value> {
     None
 }
 
+/// Whether `input` contains a JSON object whose braces have fully closed,
+/// using the same brace-depth/string-tracking state machine as
+/// [`find_first_json_object`], without actually parsing the result.
+fn json_object_closed(input: &str) -> bool {
+    let Some(start) = input.find('{') else {
+        return false;
+    };
+    let bytes = input.as_bytes();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escape_next = false;
+
+    for &b in &bytes[start..] {
+        if escape_next {
+            escape_next = false;
+            continue;
+        }
+
+        match b {
+            b'\\' if in_string => escape_next = true,
+            b'"' => in_string = !in_string,
+            b'{' if !in_string => depth += 1,
+            b'}' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -203,4 +299,52 @@ This will list all files in the home directory."#;
         let calls = result.unwrap();
         assert_eq!(calls.len(), 1);
     }
+
+    #[test]
+    fn test_llama_parser_streaming_incomplete_then_complete() {
+        let parser = LlamaToolCallParser;
+        assert_eq!(
+            parser.parse_streaming(r#"{"name": "get_weather""#),
+            ToolCallParseState::Incomplete
+        );
+
+        let complete = r#"{"name": "get_weather", "parameters": {"location": "Toronto"}}"#;
+        match parser.parse_streaming(complete) {
+            ToolCallParseState::Complete(calls) => assert_eq!(calls.len(), 1),
+            other => panic!("expected Complete, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_llama_parser_streaming_plain_text() {
+        let parser = LlamaToolCallParser;
+        assert_eq!(
+            parser.parse_streaming("Hello! How can I"),
+            ToolCallParseState::PlainText
+        );
+    }
+
+    #[test]
+    fn test_qwen3_parser_streaming_withholds_until_closed() {
+        let parser = Qwen3ToolCallParser;
+        assert_eq!(
+            parser.parse_streaming("<tool_call>\n{\"name\": \"get_weather\""),
+            ToolCallParseState::Incomplete
+        );
+
+        let complete = "<tool_call>\n{\"name\": \"get_weather\", \"arguments\": {\"location\": \"Toronto\"}}\n</tool_call>";
+        match parser.parse_streaming(complete) {
+            ToolCallParseState::Complete(calls) => assert_eq!(calls.len(), 1),
+            other => panic!("expected Complete, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_qwen3_parser_streaming_plain_text() {
+        let parser = Qwen3ToolCallParser;
+        assert_eq!(
+            parser.parse_streaming("The weather in Toronto"),
+            ToolCallParseState::PlainText
+        );
+    }
 }
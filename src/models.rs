@@ -41,7 +41,170 @@ pub struct QueryConfig {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct QueryMessage {
     pub role: String,
-    pub content: String,
+    pub content: MessageContent,
+}
+
+/// A single piece of a [`MessageContent::Parts`] message.
+///
+/// Only plain text is supported today; this exists so future part kinds
+/// (images, ...) can be added without another breaking change to
+/// [`MessageContent`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+}
+
+/// The content of a [`QueryMessage`].
+///
+/// Plain user/assistant turns are just text, but an assistant turn that
+/// issued tool calls, or a `"tool"` turn carrying a tool's result, needs to
+/// carry that structure through to the next render so the chat template can
+/// emit the `tool_calls`/`tool_call_id` fields models expect.
+///
+/// Serializes/deserializes as a bare JSON string for [`Self::Text`] (so
+/// existing callers sending `content: "hello"` keep working), and as a
+/// tagged object for every other variant.
+#[derive(Debug, Clone)]
+pub enum MessageContent {
+    Text(String),
+    ToolCalls(Vec<ToolCall>),
+    ToolResult { call_id: String, content: String },
+    Parts(Vec<ContentPart>),
+}
+
+/// Mirrors the wire shapes [`MessageContent`] accepts/produces. Kept private;
+/// `untagged` tries each variant in order, so a bare string is only ever
+/// matched by [`Self::Text`].
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawMessageContent {
+    ToolCalls {
+        tool_calls: Vec<ToolCall>,
+    },
+    ToolResult {
+        tool_call_id: String,
+        content: String,
+    },
+    Parts {
+        parts: Vec<ContentPart>,
+    },
+    Text(String),
+}
+
+impl From<MessageContent> for RawMessageContent {
+    fn from(content: MessageContent) -> Self {
+        match content {
+            MessageContent::Text(text) => RawMessageContent::Text(text),
+            MessageContent::ToolCalls(tool_calls) => RawMessageContent::ToolCalls { tool_calls },
+            MessageContent::ToolResult { call_id, content } => RawMessageContent::ToolResult {
+                tool_call_id: call_id,
+                content,
+            },
+            MessageContent::Parts(parts) => RawMessageContent::Parts { parts },
+        }
+    }
+}
+
+impl From<RawMessageContent> for MessageContent {
+    fn from(raw: RawMessageContent) -> Self {
+        match raw {
+            RawMessageContent::Text(text) => MessageContent::Text(text),
+            RawMessageContent::ToolCalls { tool_calls } => MessageContent::ToolCalls(tool_calls),
+            RawMessageContent::ToolResult {
+                tool_call_id,
+                content,
+            } => MessageContent::ToolResult {
+                call_id: tool_call_id,
+                content,
+            },
+            RawMessageContent::Parts { parts } => MessageContent::Parts(parts),
+        }
+    }
+}
+
+impl Serialize for MessageContent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        RawMessageContent::from(self.clone()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MessageContent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RawMessageContent::deserialize(deserializer).map(MessageContent::into)
+    }
+}
+
+/// A tool/function call requested by the model.
+///
+/// Produced by a [`crate::runtime::tool_call::ToolCallParser`] once it finds
+/// a complete call in the model's decoded output.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+impl ToolCall {
+    pub fn new(id: String, name: String, arguments: serde_json::Value) -> Self {
+        Self {
+            id,
+            name,
+            arguments,
+        }
+    }
+}
+
+/// A document returned by `search_documents`, ranked by similarity to the query.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DocumentMatch {
+    pub id: String,
+    pub text: String,
+}
+
+/// Timing for a single generation, recorded around the sampling loop.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GenerationMetrics {
+    pub prompt_tokens: usize,
+    pub generated_tokens: usize,
+    pub prompt_eval_seconds: f64,
+    pub decode_seconds: f64,
+}
+
+impl GenerationMetrics {
+    pub fn prompt_tokens_per_second(&self) -> f64 {
+        if self.prompt_eval_seconds > 0.0 {
+            self.prompt_tokens as f64 / self.prompt_eval_seconds
+        } else {
+            0.0
+        }
+    }
+
+    pub fn decode_tokens_per_second(&self) -> f64 {
+        if self.decode_seconds > 0.0 {
+            self.generated_tokens as f64 / self.decode_seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Response to the `runtime_status` command: device/model info plus the
+/// last-completed generation's metrics and an in-flight snapshot, if a
+/// generation is currently running.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RuntimeStatus {
+    pub device: String,
+    pub model_name: String,
+    pub last: Option<GenerationMetrics>,
+    pub in_flight: Option<GenerationMetrics>,
 }
 
 // #[derive(Serialize, Deserialize, Debug, Clone)]
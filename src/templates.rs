@@ -16,6 +16,7 @@ pub enum TemplateType {
     #[default]
     Jinja,
     Go,
+    Handlebars,
     Unknown,
 }
 
@@ -23,8 +24,9 @@ impl TemplateType {
     /// Tries to detect template type.
     ///
     /// For LLMs `jinja` seems to be the common choice for chat templates. However, some models
-    /// are using Go Templates. This function accepts a source template and tries to build it using
-    /// the provided template engines. If all detection methods fail, [`Self::Unknown`] is being returned.
+    /// are using Go Templates, and some custom prompt packs ship Handlebars-style templates.
+    /// This function accepts a source template and tries to build it using the provided template
+    /// engines. If all detection methods fail, [`Self::Unknown`] is being returned.
     ///
     /// Use this function in case the template type is unknown, or requires active detection. Normally, you
     /// wouldn't use this function.
@@ -41,6 +43,20 @@ impl TemplateType {
             render_template(source, &input_json).map(|_| Self::Go)
         } {
             return inner;
+        } else if let Ok(inner) = {
+            // minijinja also uses `{{ ... }}` for expressions but rejects
+            // Handlebars-only block helpers (`{{#each}}`, `{{#if}}`, ...), so
+            // it already failed above whenever this branch can succeed.
+            if source.contains("{{") {
+                let mut hb = handlebars::Handlebars::new();
+                hb.register_template_string("detect", source)
+                    .map_err(|e| Error::TemplateError(e.to_string()))
+                    .map(|_| Self::Handlebars)
+            } else {
+                Err(Error::TemplateError("no mustache syntax found".to_owned()))
+            }
+        } {
+            return inner;
         }
 
         Self::Unknown
@@ -69,6 +85,12 @@ impl TemplateProcessor {
         }
     }
 
+    pub fn with_handlebars_template() -> Self {
+        Self {
+            kind: TemplateType::Handlebars,
+        }
+    }
+
     pub fn from_raw_template(input: String) -> Result<Self, Error> {
         let kind = TemplateType::detect(&input);
 
@@ -86,6 +108,7 @@ impl TemplateProcessor {
         match self.kind {
             TemplateType::Go => self.render_go_template(source, input),
             TemplateType::Jinja => self.render_jinja_template(source, input),
+            TemplateType::Handlebars => self.render_handlebars_template(source, input),
             TemplateType::Unknown => Err(Error::TemplateError("Unknown template type".to_owned())),
         }
     }
@@ -110,6 +133,15 @@ impl TemplateProcessor {
             .render(input)
             .map_err(|e| Error::TemplateError(e.to_string()))
     }
+
+    fn render_handlebars_template(&self, source: &str, input: &str) -> Result<String, Error> {
+        let mut hb = handlebars::Handlebars::new();
+        hb.register_template_string("handlebars", source)
+            .map_err(|e| Error::TemplateError(e.to_string()))?;
+
+        hb.render("handlebars", input)
+            .map_err(|e| Error::TemplateError(e.to_string()))
+    }
 }
 
 /// Takes a Go template as &str, applies the json variables into it and returns the rendered template
@@ -15,19 +15,28 @@ pub fn enable_logging() {
 /// in the local cache, downloading them if necessary.
 ///
 /// Downloads: `config.json`, `tokenizer.json`, `tokenizer_config.json` (optional),
-/// and either `model.safetensors` (single file) or sharded weights via
+/// and either a quantized `gguf_file` (if given), or full-precision weights via
+/// `model.safetensors` (single file) or sharded weights via
 /// `model.safetensors.index.json`.
 #[allow(dead_code)]
 pub fn ensure_model_downloaded(
     model_id: &str,
     cache_dir: &Path,
+    gguf_file: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use hf_hub::api::sync::ApiBuilder;
 
     // Check if the model is already cached by looking for config.json
     let cache = hf_hub::Cache::new(cache_dir.to_path_buf());
     let cache_repo = cache.model(model_id.to_string());
-    if cache_repo.get("config.json").is_some() && cache_repo.get("tokenizer.json").is_some() {
+    let weights_cached = match gguf_file {
+        Some(gguf_file) => cache_repo.get(gguf_file).is_some(),
+        None => cache_repo.get("model.safetensors").is_some(),
+    };
+    if cache_repo.get("config.json").is_some()
+        && cache_repo.get("tokenizer.json").is_some()
+        && weights_cached
+    {
         // Model appears to be cached already
         return Ok(());
     }
@@ -49,6 +58,14 @@ pub fn ensure_model_downloaded(
     // Optional but useful
     let _ = repo.get("tokenizer_config.json");
 
+    if let Some(gguf_file) = gguf_file {
+        // Quantized weights are a single pre-built file; skip safetensors
+        // resolution entirely.
+        repo.get(gguf_file)?;
+        eprintln!("Model '{model_id}' download complete.");
+        return Ok(());
+    }
+
     // Try single-file model first
     if repo.get("model.safetensors").is_err() {
         // Sharded model: download the index and each shard
@@ -1,12 +1,110 @@
 use anyhow::{Error as E, Result};
-use candle_core::{quantized::gguf_file, Tensor};
+use candle_core::{quantized::gguf_file, Device, Tensor};
 use candle_transformers::{
     generation::{LogitsProcessor, Sampling},
-    models::quantized_qwen3::ModelWeights as Qwen3,
+    models::{
+        quantized_llama::ModelWeights as QuantizedLlama, quantized_phi::ModelWeights as QuantizedPhi,
+        quantized_qwen3::ModelWeights as Qwen3,
+    },
+    utils::apply_repeat_penalty,
 };
 use std::fs::File;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri_plugin_llm::runtime::{select_device, DevicePreference};
 use tokenizers::Tokenizer;
 
+/// Sampling and repeat-penalty knobs for a single generation.
+struct GenerationConfig {
+    temperature: f64,
+    top_p: f64,
+    top_k: usize,
+    seed: u64,
+    repeat_penalty: f32,
+    repeat_last_n: usize,
+    max_tokens: usize,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
+            temperature: 0.9,
+            top_p: 1.0,
+            top_k: 10,
+            seed: 0xBAD666,
+            repeat_penalty: 1.1,
+            repeat_last_n: 64,
+            max_tokens: 1000,
+        }
+    }
+}
+
+impl GenerationConfig {
+    /// `ArgMax` when `temperature` is `0`, otherwise top-k, adding nucleus
+    /// filtering on top whenever `top_p` narrows the distribution.
+    fn sampling(&self) -> Sampling {
+        if self.temperature == 0.0 {
+            Sampling::ArgMax
+        } else if self.top_p < 1.0 {
+            Sampling::TopKThenTopP {
+                k: self.top_k,
+                p: self.top_p,
+                temperature: self.temperature,
+            }
+        } else {
+            Sampling::TopK {
+                k: self.top_k,
+                temperature: self.temperature,
+            }
+        }
+    }
+}
+
+/// Supported GGUF architectures, dispatched from the `general.architecture`
+/// metadata key. To support another `candle_transformers::models::quantized_*`
+/// family, add a variant here and a matching arm in [`Self::from_gguf`] and
+/// [`Self::forward`].
+enum Model {
+    Qwen3(Qwen3),
+    Llama(QuantizedLlama),
+    Phi(QuantizedPhi),
+}
+
+impl Model {
+    /// Reads `general.architecture` from `content` and loads the matching
+    /// quantized weights. Returns an error — rather than panicking — when the
+    /// architecture is unrecognized or `from_gguf` fails because a required
+    /// metadata key (e.g. `*.attention.head_count`) is missing.
+    fn from_gguf(content: gguf_file::Content, file: &mut File, device: &Device) -> Result<Self> {
+        let architecture = content
+            .metadata
+            .get("general.architecture")
+            .and_then(|value| value.to_string().ok())
+            .ok_or_else(|| E::msg("GGUF file is missing the general.architecture metadata key"))?
+            .to_string();
+
+        match architecture.as_str() {
+            "qwen3" => Ok(Self::Qwen3(Qwen3::from_gguf(content, file, device)?)),
+            "llama" => Ok(Self::Llama(QuantizedLlama::from_gguf(content, file, device)?)),
+            "phi" | "phi2" | "phi3" => {
+                Ok(Self::Phi(QuantizedPhi::from_gguf(content, file, device)?))
+            }
+            other => Err(E::msg(format!(
+                "Unsupported GGUF architecture '{other}'; add a Model variant for it"
+            ))),
+        }
+    }
+
+    fn forward(&mut self, input: &Tensor, pos: usize) -> Result<Tensor> {
+        let logits = match self {
+            Self::Qwen3(model) => model.forward(input, pos)?,
+            Self::Llama(model) => model.forward(input, pos)?,
+            Self::Phi(model) => model.forward(input, pos)?,
+        };
+
+        Ok(logits)
+    }
+}
+
 struct TokenStream {
     tokenizer: Tokenizer,
     tokens: Vec<u32>,
@@ -35,33 +133,111 @@ impl TokenStream {
         self.decode(&self.tokens)
     }
 
+    /// Pushes a newly sampled token and returns the delta text, if any new
+    /// complete characters were produced.
+    ///
+    /// Decodes `tokens[prev..]` and compares its byte length against the
+    /// previously decoded prefix, flushing the new bytes once they form a
+    /// complete, non-`U+FFFD` UTF-8 string. This holds back a partial
+    /// multibyte sequence (CJK, emoji, ...) instead of checking whether the
+    /// last decoded `char` happens to be alphanumeric, which silently drops
+    /// whitespace, punctuation, and any script whose final codepoint isn't
+    /// alphanumeric.
     pub fn next_token(&mut self, token: u32) -> Result<Option<String>> {
-        let prev_text = if self.tokens.is_empty() {
-            String::new()
-        } else {
-            let tokens = &self.tokens[self.prev..self.current];
-            self.decode(tokens)?
-        };
+        let prev_text = self.decode(&self.tokens[self.prev..self.current])?;
 
         self.tokens.push(token);
 
         let text = self.decode(&self.tokens[self.prev..])?;
 
-        if text.len() > prev_text.len() && text.chars().last().unwrap().is_alphanumeric() {
-            let text = text.split_at(prev_text.len());
+        if text.len() > prev_text.len() && !text.ends_with('\u{fffd}') {
             self.prev = self.current;
             self.current = self.tokens.len();
-            Ok(Some(text.1.to_string()))
+            Ok(Some(text[prev_text.len()..].to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Flushes any trailing bytes withheld because they hadn't closed into a
+    /// complete character yet.
+    pub fn finalize(&mut self) -> Result<Option<String>> {
+        let prev_text = self.decode(&self.tokens[self.prev..self.current])?;
+        let text = self.decode(&self.tokens[self.prev..])?;
+
+        if text.len() > prev_text.len() {
+            Ok(Some(text[prev_text.len()..].to_string()))
         } else {
             Ok(None)
         }
     }
 }
 
+/// Runs generation, invoking `on_chunk` with each newly decoded text fragment
+/// as soon as it's produced instead of buffering the whole output until the
+/// end. `cancel` is checked once per iteration so a caller on another thread
+/// (e.g. a closed Tauri `Channel`) can abort an in-flight generation.
+fn generate_streaming(
+    model: &mut Model,
+    device: &Device,
+    tokens: &[u32],
+    config: &GenerationConfig,
+    tos: &mut TokenStream,
+    eos_token: u32,
+    cancel: &AtomicBool,
+    mut on_chunk: impl FnMut(&str),
+) -> Result<()> {
+    let mut logits_processor = LogitsProcessor::from_sampling(config.seed, config.sampling());
+
+    let mut next_token = {
+        let input = Tensor::new(tokens, device)?.unsqueeze(0)?;
+        let logits = model.forward(&input, 0)?;
+        let logits = logits.squeeze(0)?;
+        logits_processor.sample(&logits)?
+    };
+
+    let mut all_tokens = vec![next_token];
+
+    if let Some(text) = tos.next_token(next_token)? {
+        on_chunk(&text);
+    }
+
+    for index in 0..config.max_tokens {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let input = Tensor::new(&[next_token], device)?.unsqueeze(0)?;
+        let logits = model.forward(&input, tokens.len() + index)?;
+        let logits = logits.squeeze(0)?;
+
+        let penalty_start = all_tokens.len().saturating_sub(config.repeat_last_n);
+        let logits = apply_repeat_penalty(
+            &logits,
+            config.repeat_penalty,
+            &all_tokens[penalty_start..],
+        )?;
+
+        next_token = logits_processor.sample(&logits)?;
+        all_tokens.push(next_token);
+
+        if let Some(text) = tos.next_token(next_token)? {
+            on_chunk(&text);
+        }
+
+        if next_token == eos_token {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
 #[test]
 #[ignore = "This test relies on external dependencies that must exists prior to test execution. Check the code which files are necessary"]
 fn test_load_and_execute_model() -> anyhow::Result<()> {
-    let device = candle_core::Device::new_metal(0)?;
+    let (device, device_label) = select_device(DevicePreference::Auto)?;
+    println!("Running on {device_label}");
 
     println!("Loading tokenzier");
     let tokenizer_file_path = "./models/Qwen3-4B-Instruct-2507-FP8/tokenizer.json";
@@ -71,10 +247,10 @@ fn test_load_and_execute_model() -> anyhow::Result<()> {
     let model_file_path = "./models/Qwen3-4B-GGUF/Qwen3-4B-Q4_K_M.gguf";
     let mut model = {
         let mut model_file = File::open(model_file_path)?;
-        let model = gguf_file::Content::read(&mut model_file)
+        let content = gguf_file::Content::read(&mut model_file)
             .map_err(|e| E::msg(format!("Could not read model file{}", e)))?;
 
-        Qwen3::from_gguf(model, &mut model_file, &device)?
+        Model::from_gguf(content, &mut model_file, &device)?
     };
 
     println!("Model build");
@@ -90,52 +266,78 @@ fn test_load_and_execute_model() -> anyhow::Result<()> {
 
     let tokens = tokens.get_ids();
 
-    // number of tokens to generate
-    let to_sample = 1000;
-    // let repeat_penalty = 1.;
-
-    let mut logits_processor = {
-        let sampling = Sampling::TopK {
-            k: 10,
-            temperature: 0.9,
-        };
-
-        LogitsProcessor::from_sampling(0xBAD666, sampling)
-    };
+    let config = GenerationConfig::default();
+    let eos_token = *tos.tokenizer.get_vocab(true).get("<|im_end|>").unwrap();
 
-    let mut next_token = {
-        let input = Tensor::new(tokens, &device)?.unsqueeze(0)?;
-        let logits = model.forward(&input, 0)?;
-        let logits = logits.squeeze(0)?;
-        logits_processor.sample(&logits)?
-    };
+    // In production this is flipped from another thread when the frontend's
+    // Tauri `Channel` closes, letting the UI cancel a generation mid-stream.
+    let cancel = AtomicBool::new(false);
 
-    let mut all_tokens = vec![];
+    generate_streaming(
+        &mut model,
+        &device,
+        tokens,
+        &config,
+        &mut tos,
+        eos_token,
+        &cancel,
+        |chunk| print!("{chunk}"),
+    )?;
 
-    all_tokens.push(next_token);
+    println!("\nResult: {}", tos.decode_all()?);
 
-    let eos_token = *tos.tokenizer.get_vocab(true).get("<|im_end|>").unwrap();
+    Ok(())
+}
 
-    for index in 0..to_sample {
-        let input = Tensor::new(&[next_token], &device)?.unsqueeze(0)?;
-        let logits = model.forward(&input, tokens.len() + index)?;
-        let logits = logits.squeeze(0)?;
+/// Encodes `text`, feeds it through [`TokenStream`] one token at a time, and
+/// asserts that no streamed chunk (or the final [`TokenStream::finalize`]
+/// flush) ever contains a `U+FFFD` replacement character, and that the
+/// concatenation of every chunk matches [`TokenStream::decode_all`].
+fn assert_token_stream_roundtrip(text: &str) -> anyhow::Result<()> {
+    let tokenizer_file_path = "./models/Qwen3-4B-Instruct-2507-FP8/tokenizer.json";
+    let tokenizer = Tokenizer::from_file(tokenizer_file_path)
+        .map_err(|e| E::msg(format!("Could not load tokenizer {}", e)))?;
 
-        // skip applying repeat penalty for now
+    let ids = tokenizer
+        .encode(text, false)
+        .map_err(|e| E::msg(format!("Error encoding text {}", e)))?
+        .get_ids()
+        .to_vec();
 
-        next_token = logits_processor.sample(&logits)?;
-        all_tokens.push(next_token);
+    let mut tos = TokenStream::new(tokenizer);
+    let mut streamed = String::new();
 
-        if let Some(_) = tos.next_token(next_token)? {
-            print!("processing ...\r");
+    for id in ids {
+        if let Some(chunk) = tos.next_token(id)? {
+            assert!(!chunk.contains('\u{fffd}'));
+            streamed.push_str(&chunk);
         }
+    }
 
-        if next_token == eos_token {
-            break;
-        }
+    if let Some(chunk) = tos.finalize()? {
+        assert!(!chunk.contains('\u{fffd}'));
+        streamed.push_str(&chunk);
     }
 
-    println!("Result: {}", tos.decode_all()?);
+    assert_eq!(streamed, tos.decode_all()?);
 
     Ok(())
 }
+
+#[test]
+#[ignore = "This test relies on external dependencies that must exists prior to test execution. Check the code which files are necessary"]
+fn test_token_stream_whitespace_roundtrip() -> anyhow::Result<()> {
+    assert_token_stream_roundtrip("one two   three\nfour\tfive")
+}
+
+#[test]
+#[ignore = "This test relies on external dependencies that must exists prior to test execution. Check the code which files are necessary"]
+fn test_token_stream_cjk_roundtrip() -> anyhow::Result<()> {
+    assert_token_stream_roundtrip("你好，世界！这是一个测试。")
+}
+
+#[test]
+#[ignore = "This test relies on external dependencies that must exists prior to test execution. Check the code which files are necessary"]
+fn test_token_stream_emoji_roundtrip() -> anyhow::Result<()> {
+    assert_token_stream_roundtrip("Great job! 🎉🚀😀 Keep going.")
+}